@@ -0,0 +1,132 @@
+use std::io::{BufRead, Write};
+use std::process::ExitCode;
+
+use clap::{Arg, ArgAction, ArgMatches, Command};
+
+use xdb::{CachePolicy, Ip2RegionError, IpInfo, Searcher, SearcherBuilder};
+
+fn command() -> Command {
+    let command = Command::new("ip2region-cli")
+        .version("0.1")
+        .about("search the region of ips, one result per line")
+        .arg(
+            Arg::new("db")
+                .long("db")
+                .help("the xdb filepath, detect ../data/ip2region.xdb, ../../data/ip2region.xdb, ../../../data/ip2region.xdb if not set"),
+        )
+        .arg(
+            Arg::new("cache-policy")
+                .long("cache-policy")
+                .value_parser(["never", "vec_index", "full"])
+                .default_value("full")
+                .help("how much of the xdb file is kept in memory"),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_parser(["text", "json"])
+                .default_value("text")
+                .help("text prints `ip region`, json needs the serde feature"),
+        )
+        .arg(
+            Arg::new("from-stdin")
+                .long("from-stdin")
+                .action(ArgAction::SetTrue)
+                .help("read the ips from stdin, one ip per line"),
+        )
+        .arg(
+            Arg::new("ips")
+                .action(ArgAction::Append)
+                .help("the ipv4 or ipv6 addresses to search"),
+        );
+    #[cfg(feature = "cli")]
+    let command = command.arg(
+        Arg::new("completions")
+            .long("completions")
+            .value_parser(clap::value_parser!(clap_complete::Shell))
+            .help("print the completion script of the shell and exit"),
+    );
+    command
+}
+
+fn search(searcher: &Searcher, ip: &str) -> Result<IpInfo, Ip2RegionError> {
+    if ip.contains(':') {
+        searcher.search_by_ipv6(ip)
+    } else {
+        searcher.search(ip)
+    }
+}
+
+#[cfg(feature = "serde")]
+fn json_line(ip: &str, info: &IpInfo) -> String {
+    serde_json::json!({ "ip": ip, "region": info }).to_string()
+}
+
+#[cfg(not(feature = "serde"))]
+fn json_line(_: &str, _: &IpInfo) -> String {
+    unreachable!("json format is rejected without the serde feature")
+}
+
+fn run(matches: &ArgMatches) -> Result<bool, Ip2RegionError> {
+    let json = matches.get_one::<String>("format").map(String::as_str) == Some("json");
+    if json && cfg!(not(feature = "serde")) {
+        eprintln!("--format json needs the serde feature");
+        return Ok(false);
+    }
+    let cache_policy: CachePolicy = matches.get_one::<String>("cache-policy").unwrap().parse()?;
+    let mut builder = SearcherBuilder::new().cache_policy(cache_policy);
+    if let Some(xdb_filepath) = matches.get_one::<String>("db") {
+        builder = builder.path(xdb_filepath);
+    }
+    let searcher = builder.build()?;
+
+    let mut ips: Vec<String> = matches
+        .get_many::<String>("ips")
+        .unwrap_or_default()
+        .cloned()
+        .collect();
+    if matches.get_flag("from-stdin") {
+        for line in std::io::stdin().lock().lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                ips.push(line.trim().to_owned());
+            }
+        }
+    }
+
+    let mut all_found = true;
+    let mut stdout = std::io::stdout().lock();
+    for ip in &ips {
+        match search(&searcher, ip) {
+            Ok(info) if json => writeln!(stdout, "{}", json_line(ip, &info))?,
+            Ok(info) => writeln!(stdout, "{ip} {info}")?,
+            Err(e) => {
+                all_found = false;
+                eprintln!("{ip} {e}");
+            }
+        }
+    }
+    Ok(all_found)
+}
+
+fn main() -> ExitCode {
+    let matches = command().get_matches();
+    #[cfg(feature = "cli")]
+    if let Some(shell) = matches.get_one::<clap_complete::Shell>("completions") {
+        clap_complete::generate(
+            *shell,
+            &mut command(),
+            "ip2region-cli",
+            &mut std::io::stdout(),
+        );
+        return ExitCode::SUCCESS;
+    }
+    match run(&matches) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
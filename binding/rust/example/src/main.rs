@@ -54,7 +54,7 @@ fn bench_test(src_filepath: &str) {
             end_ip,
         ] {
             let result = search_by_ip(ip).unwrap();
-            assert_eq!(result.as_str(), ip_test_line[2]);
+            assert_eq!(result.raw(), ip_test_line[2]);
             count += 1;
         }
     }
@@ -79,17 +79,17 @@ fn query_test() {
         }
         let line = line.trim();
         let now = Instant::now();
-        let result = search_by_ip(line);
+        let result = search_by_ip(line).map(|info| info.raw());
         let cost = now.elapsed();
         println!("region: {result:?}, took: {cost:?}",);
     }
 }
 
 fn matches_for_searcher(matches: &ArgMatches) {
-    if let Some(xdb_filepath) = matches.get_one::<String>("db") {
-        searcher_init(Some(xdb_filepath.to_owned()))
-    } else {
-        searcher_init(None);
+    let xdb_filepath = matches.get_one::<String>("db").cloned();
+    if let Err(e) = searcher_init(xdb_filepath) {
+        eprintln!("load the xdb file error: {e}");
+        std::process::exit(1);
     }
 }
 
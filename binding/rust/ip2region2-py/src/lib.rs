@@ -0,0 +1,73 @@
+//! the python module `ip2region2` of the rust binding, build it with
+//! `maturin develop -m binding/rust/ip2region2-py/Cargo.toml`
+//!
+//! ```python
+//! from ip2region2 import Searcher
+//!
+//! searcher = Searcher("data/ip2region.xdb", "vec_index")
+//! searcher.search("1.0.1.0")["province"]  # 福建省
+//! ```
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+
+use pyo3::exceptions::{PyFileNotFoundError, PyKeyError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use xdb::{CachePolicy, Ip2RegionError};
+
+/// the `xdb::Searcher` of a xdb file, it is shared by the python threads and the searches
+/// release the gil
+#[pyclass(name = "Searcher", module = "ip2region2", frozen)]
+struct PySearcher {
+    searcher: xdb::Searcher,
+}
+
+#[pymethods]
+impl PySearcher {
+    /// load the xdb file at `path`, `cache_policy` is one of `never`, `vec_index` and `full`
+    #[new]
+    #[pyo3(signature = (path, cache_policy = "full"))]
+    fn new(py: Python<'_>, path: PathBuf, cache_policy: &str) -> PyResult<Self> {
+        let cache_policy: CachePolicy = cache_policy.parse().map_err(to_py_err)?;
+        let searcher = py
+            .allow_threads(|| xdb::Searcher::with_cache_policy(path, cache_policy))
+            .map_err(to_py_err)?;
+        Ok(Self { searcher })
+    }
+
+    /// the dict of `country`, `region`, `province`, `city` and `isp` of the ipv4 `ip`, an ip
+    /// not in the xdb file raises a `KeyError`
+    fn search(&self, py: Python<'_>, ip: &str) -> PyResult<HashMap<String, String>> {
+        py.allow_threads(|| self.searcher.search_as_map(ip))
+            .map_err(to_py_err)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Searcher(cache_policy='{}')", self.searcher.cache_policy())
+    }
+}
+
+/// the builtin python exception closest to the error, the message is followed by its rust
+/// sources which python does not see otherwise
+fn to_py_err(e: Ip2RegionError) -> PyErr {
+    let mut message = e.to_string();
+    let mut source = e.source();
+    while let Some(cause) = source {
+        message = format!("{message}: {cause}");
+        source = cause.source();
+    }
+    match e {
+        Ip2RegionError::FileNotFound(_) => PyFileNotFoundError::new_err(message),
+        Ip2RegionError::InvalidIp(_) | Ip2RegionError::InvalidCachePolicy(_) => {
+            PyValueError::new_err(message)
+        }
+        Ip2RegionError::NotMatched => PyKeyError::new_err(message),
+        _ => PyRuntimeError::new_err(message),
+    }
+}
+
+#[pymodule]
+fn ip2region2(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PySearcher>()?;
+    Ok(())
+}
@@ -0,0 +1,30 @@
+# run with `maturin develop && python -m pytest tests` in binding/rust/ip2region2-py
+import os
+
+import pytest
+
+from ip2region2 import Searcher
+
+XDB_PATH = os.path.join(os.path.dirname(__file__), "../../../../data/ip2region.xdb")
+
+
+@pytest.mark.parametrize("cache_policy", ["never", "vec_index", "full"])
+def test_search(cache_policy):
+    searcher = Searcher(XDB_PATH, cache_policy)
+    assert searcher.search("1.0.1.0") == {
+        "country": "中国",
+        "region": "0",
+        "province": "福建省",
+        "city": "福州市",
+        "isp": "电信",
+    }
+
+
+def test_errors():
+    searcher = Searcher(XDB_PATH)
+    with pytest.raises(ValueError):
+        searcher.search("1.0.1")
+    with pytest.raises(ValueError):
+        Searcher(XDB_PATH, "everything")
+    with pytest.raises(FileNotFoundError):
+        Searcher("not_exists.xdb")
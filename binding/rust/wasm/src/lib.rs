@@ -0,0 +1,3 @@
+//! the `cdylib` of the `wasm` feature of xdb, build it with
+//! `wasm-pack build --target web binding/rust/wasm`
+pub use xdb::{init, search_by_ip_js, WasmSearcher};
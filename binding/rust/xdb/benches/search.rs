@@ -1,24 +1,108 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use rand;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
 
-use xdb::searcher::{
-    get_block_by_size, get_full_cache, get_vector_index_cache, search_by_ip, searcher_init,
-};
+use xdb::searcher::{get_block_by_size, search_by_ip, searcher_init};
+use xdb::{core_search, CachePolicy, Searcher};
+
+const XDB_FILEPATH: &str = "../../../data/ip2region.xdb";
 
 fn search_by_ip_bench(c: &mut Criterion) {
     c.bench_function("search_by_ip_bench", |b| {
-        searcher_init(None);
+        searcher_init(None).unwrap();
         b.iter(|| {
             search_by_ip(rand::random::<u32>()).unwrap();
         })
     });
 }
 
+fn searcher_search_bench(c: &mut Criterion) {
+    let searcher = Searcher::new(XDB_FILEPATH).unwrap();
+    c.bench_function("searcher_search_bench", |b| {
+        b.iter(|| {
+            searcher.search(rand::random::<u32>()).unwrap();
+        })
+    });
+}
+
+fn cache_policies() -> Vec<CachePolicy> {
+    vec![
+        CachePolicy::Never,
+        CachePolicy::VecIndex,
+        CachePolicy::Full,
+        #[cfg(feature = "memmap2")]
+        CachePolicy::Mmap,
+    ]
+}
+
+/// a single search of a searcher kept across the searches, for picking a cache policy
+fn cache_policy_search_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cache_policy_search_bench");
+    for cache_policy in cache_policies() {
+        let searcher = Searcher::with_cache_policy(XDB_FILEPATH, cache_policy).unwrap();
+        group.bench_function(BenchmarkId::from_parameter(cache_policy), |b| {
+            b.iter(|| black_box(searcher.search(rand::random::<u32>()).unwrap()))
+        });
+    }
+    group.finish();
+}
+
+/// a single search of a searcher created for it and dropped after it, against a searcher kept
+/// warm, the cold search pays for loading the cache of the policy and the page cache misses
+fn cold_warm_search_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cold_warm_search_bench");
+    group.sample_size(20);
+    for cache_policy in cache_policies() {
+        group.bench_function(BenchmarkId::new("cold", cache_policy), |b| {
+            b.iter(|| {
+                let searcher = Searcher::with_cache_policy(XDB_FILEPATH, cache_policy).unwrap();
+                black_box(searcher.search(rand::random::<u32>()).unwrap())
+            })
+        });
+        let searcher = Searcher::with_cache_policy(XDB_FILEPATH, cache_policy).unwrap();
+        group.bench_function(BenchmarkId::new("warm", cache_policy), |b| {
+            b.iter(|| black_box(searcher.search(rand::random::<u32>()).unwrap()))
+        });
+    }
+    group.finish();
+}
+
+/// the dotted-decimal string parsing of `search_str` against the generic `search_by_ip`
+fn search_str_bench(c: &mut Criterion) {
+    searcher_init(None).unwrap();
+    let searcher = Searcher::new(XDB_FILEPATH).unwrap();
+    c.bench_function("search_by_ip_str_bench", |b| {
+        b.iter(|| black_box(search_by_ip(black_box("1.1.1.1")).unwrap()))
+    });
+    c.bench_function("searcher_search_str_bench", |b| {
+        b.iter(|| black_box(searcher.search_str(black_box("1.1.1.1")).unwrap()))
+    });
+}
+
+/// only the binary search over the whole xdb file, without the region parsing of `search`,
+/// count its instructions by `perf stat -e instructions` over
+/// `cargo bench --bench search -- --profile-time 10 core_search_bench`
+fn core_search_bench(c: &mut Criterion) {
+    let buffer = std::fs::read(XDB_FILEPATH).unwrap();
+    c.bench_function("core_search_bench", |b| {
+        b.iter(|| black_box(core_search(&buffer, black_box(rand::random::<u32>())).unwrap()))
+    });
+}
+
+fn searcher_search_raw_bench(c: &mut Criterion) {
+    let searcher = Searcher::new(XDB_FILEPATH).unwrap();
+    c.bench_function("searcher_search_raw_bench", |b| {
+        b.iter(|| {
+            black_box(searcher.search_raw(rand::random::<u32>()).unwrap());
+        })
+    });
+}
+
 fn get_block_by_size_bench(c: &mut Criterion) {
+    let searcher = Searcher::new(XDB_FILEPATH).unwrap();
+    let buffer = searcher.get_full_cache().unwrap();
     c.bench_function("get_block_by_size_bench", |b| {
         b.iter(|| {
             black_box(get_block_by_size(
-                get_full_cache(),
+                &buffer,
                 rand::random::<u16>() as usize,
                 4,
             ));
@@ -27,26 +111,120 @@ fn get_block_by_size_bench(c: &mut Criterion) {
 }
 
 fn get_full_cache_bench(c: &mut Criterion) {
+    let searcher = Searcher::new(XDB_FILEPATH).unwrap();
     c.bench_function("get_full_cache_bench", |b| {
         b.iter(|| {
-            black_box(get_full_cache());
+            black_box(searcher.get_full_cache().unwrap());
         })
     });
 }
 
 fn get_vec_index_cache_bench(c: &mut Criterion) {
+    let searcher = Searcher::new(XDB_FILEPATH).unwrap();
     c.bench_function("get_vec_index_cache_bench", |b| {
         b.iter(|| {
-            black_box(get_vector_index_cache());
+            black_box(searcher.get_vector_index_cache().unwrap());
         })
     });
 }
 
+/// load the xdb file and search once, the cold start cost of a searcher
+fn searcher_load_bench(c: &mut Criterion) {
+    c.bench_function("searcher_load_full_bench", |b| {
+        b.iter(|| {
+            let searcher = Searcher::new(XDB_FILEPATH).unwrap();
+            black_box(searcher.search(rand::random::<u32>()).unwrap());
+        })
+    });
+    #[cfg(feature = "memmap2")]
+    c.bench_function("searcher_load_mmap_bench", |b| {
+        b.iter(|| {
+            let searcher = Searcher::from_mmap(std::path::Path::new(XDB_FILEPATH)).unwrap();
+            black_box(searcher.search(rand::random::<u32>()).unwrap());
+        })
+    });
+}
+
+/// 100k searches over 10k distinct ips where the k-th most common ip is searched about
+/// 1/k as often as the first, like the client ips of an access log
+fn zipf_ips() -> Vec<u32> {
+    let distinct: Vec<u32> = (0..10_000).map(|_| rand::random()).collect();
+    let mut cumulative = Vec::with_capacity(distinct.len());
+    let mut total = 0.0;
+    for rank in 1..=distinct.len() {
+        total += 1.0 / rank as f64;
+        cumulative.push(total);
+    }
+    (0..100_000)
+        .map(|_| {
+            let weight = rand::random::<f64>() * total;
+            distinct[cumulative.partition_point(|&c| c < weight)]
+        })
+        .collect()
+}
+
+/// compare the plain searcher to the lru cached one on a zipfian workload, the cache is large
+/// enough for all the distinct ips, so it measures the hits against the binary searches
+fn zipf_search_bench(c: &mut Criterion) {
+    let ips = zipf_ips();
+    let searcher = Searcher::new(XDB_FILEPATH).unwrap();
+    c.bench_function("searcher_zipf_bench", |b| {
+        b.iter(|| {
+            for &ip in &ips {
+                black_box(searcher.search(ip).unwrap());
+            }
+        })
+    });
+    #[cfg(feature = "lru")]
+    {
+        let cached = xdb::CachedSearcher::new(Searcher::new(XDB_FILEPATH).unwrap(), 10_000);
+        c.bench_function("cached_searcher_zipf_bench", |b| {
+            b.iter(|| {
+                for &ip in &ips {
+                    black_box(cached.search(ip).unwrap());
+                }
+            })
+        });
+    }
+}
+
+/// the throughput of `parallel_search` on thread pools of 1, 2, 4 and 8 threads,
+/// it scales with the physical cores as the full cache is only read
+#[cfg(feature = "rayon")]
+fn parallel_search_bench(c: &mut Criterion) {
+    let searcher = Searcher::new(XDB_FILEPATH).unwrap();
+    let ips: Vec<u32> = (0..100_000).map(|_| rand::random()).collect();
+    let mut group = c.benchmark_group("parallel_search_bench");
+    group.throughput(criterion::Throughput::Elements(ips.len() as u64));
+    for threads in [1, 2, 4, 8] {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        group.bench_function(BenchmarkId::from_parameter(threads), |b| {
+            b.iter(|| pool.install(|| black_box(searcher.parallel_search(&ips))))
+        });
+    }
+    group.finish();
+}
+
+#[cfg(not(feature = "rayon"))]
+fn parallel_search_bench(_: &mut Criterion) {}
+
 criterion_group!(
     benches,
     search_by_ip_bench,
+    searcher_search_bench,
+    cache_policy_search_bench,
+    cold_warm_search_bench,
+    search_str_bench,
+    core_search_bench,
+    searcher_search_raw_bench,
     get_block_by_size_bench,
     get_full_cache_bench,
     get_vec_index_cache_bench,
+    searcher_load_bench,
+    zipf_search_bench,
+    parallel_search_bench,
 );
 criterion_main!(benches);
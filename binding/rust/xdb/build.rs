@@ -0,0 +1,73 @@
+//! with the `embed` feature, find the xdb file to embed and check it before it is compiled in
+
+use std::env;
+use std::path::PathBuf;
+
+const HEADER_INFO_LENGTH: usize = 256;
+const VECTOR_INDEX_LENGTH: usize = 512 * 1024;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    if env::var_os("CARGO_FEATURE_EMBED").is_none() {
+        return;
+    }
+    println!("cargo:rerun-if-env-changed=XDB_EMBED_PATH");
+    let xdb_filepath = match env::var_os("XDB_EMBED_PATH") {
+        Some(xdb_filepath) => PathBuf::from(xdb_filepath),
+        // the data/ip2region.xdb of the repository when it is built in place
+        None => PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").unwrap())
+            .join("../../../data/ip2region.xdb"),
+    };
+    let xdb_filepath = xdb_filepath.canonicalize().unwrap_or_else(|e| {
+        panic!(
+            "set XDB_EMBED_PATH to the xdb file to embed, {} is not found: {e}",
+            xdb_filepath.display()
+        )
+    });
+    println!("cargo:rerun-if-changed={}", xdb_filepath.display());
+
+    let buffer = std::fs::read(&xdb_filepath).unwrap();
+    if let Err(reason) = check_xdb(&buffer) {
+        panic!("invalid xdb file {}: {reason}", xdb_filepath.display());
+    }
+    println!("cargo:rustc-env=XDB_EMBED_FILE={}", xdb_filepath.display());
+}
+
+/// the checks of `validate_header` and the segment index bounds, the build script can not
+/// depend on the crate it builds
+fn check_xdb(buffer: &[u8]) -> Result<(), String> {
+    if buffer.len() < HEADER_INFO_LENGTH + VECTOR_INDEX_LENGTH {
+        return Err(format!("only {} bytes", buffer.len()));
+    }
+    let u16_at = |offset: usize| u16::from_le_bytes([buffer[offset], buffer[offset + 1]]);
+    let u32_at = |offset: usize| {
+        u32::from_le_bytes([
+            buffer[offset],
+            buffer[offset + 1],
+            buffer[offset + 2],
+            buffer[offset + 3],
+        ]) as usize
+    };
+    let segment_index_size = match (u16_at(0), u16_at(16)) {
+        (2, _) | (3, 4) => 14,
+        (3, 6) => 38,
+        (version, ip_version) => {
+            return Err(format!(
+                "unsupported xdb version {version} with ip version {ip_version}"
+            ))
+        }
+    };
+    if u16_at(2) != 1 {
+        return Err(format!("unsupported index policy {}", u16_at(2)));
+    }
+    let (start_index_ptr, end_index_ptr) = (u32_at(8), u32_at(12));
+    if start_index_ptr < HEADER_INFO_LENGTH + VECTOR_INDEX_LENGTH
+        || end_index_ptr < start_index_ptr
+        || end_index_ptr + segment_index_size > buffer.len()
+    {
+        return Err(format!(
+            "invalid segment index range {start_index_ptr}..={end_index_ptr}"
+        ));
+    }
+    Ok(())
+}
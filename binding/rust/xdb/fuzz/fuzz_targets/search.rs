@@ -0,0 +1,14 @@
+//! any buffer accepted by `Searcher::from_bytes` must be searched without a panic,
+//! the corrupted pointers are reported as errors
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use xdb::{core_search, Searcher};
+
+fuzz_target!(|input: (Vec<u8>, u32)| {
+    let (buffer, ip) = input;
+    let _ = core_search(&buffer, ip);
+    if let Ok(searcher) = Searcher::from_bytes(buffer) {
+        let _ = searcher.search(ip);
+    }
+});
@@ -0,0 +1,127 @@
+use std::io::SeekFrom;
+
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::format::{
+    last_segment, ptr_at, u16_at, SegmentIp, HEADER_INFO_LENGTH, IPV6_SEGMENT_INDEX_SIZE,
+    VECTOR_INDEX_SIZE,
+};
+use crate::searcher::{global_searcher, open_error};
+use crate::{Ip2RegionError, IpInfo, IpVersion, Searcher, ToUIntIP};
+
+impl Searcher {
+    /// same as `search`, but the file io of `CachePolicy::Never` is done by `tokio::fs`
+    /// so it does not block the executor, the other cache policies are memory lookups
+    /// and are searched synchronously
+    pub async fn search_async<T>(&self, ip: T) -> Result<IpInfo, Ip2RegionError>
+    where
+        T: ToUIntIP,
+    {
+        let ip = ip.to_u32_ip()?;
+        let (xdb_filepath, ip_version) = match self.never_filepath() {
+            Some(never) => never,
+            None => return self.search(ip),
+        };
+        if ip_version != IpVersion::V4 {
+            return Err(Ip2RegionError::IpVersionMismatch);
+        }
+        let mut file = File::open(&xdb_filepath)
+            .await
+            .map_err(|e| open_error(&xdb_filepath, e))?;
+        IpInfo::try_from(search_in_file(&mut file, ip).await?)
+    }
+
+    /// same as `search_hostname` but resolved by `tokio::net::lookup_host`, which runs the
+    /// blocking system resolver on the blocking thread pool of tokio
+    pub async fn search_hostname_async(&self, hostname: &str) -> Result<IpInfo, Ip2RegionError> {
+        self.search_resolved(tokio::net::lookup_host((hostname, 0)).await?)
+    }
+}
+
+/// search by the searcher initialized in `searcher_init` without blocking the executor
+pub async fn search_by_ip_async<T>(ip: T) -> Result<IpInfo, Ip2RegionError>
+where
+    T: ToUIntIP,
+{
+    global_searcher()?.search_async(ip).await
+}
+
+async fn read_at(file: &mut File, offset: usize, buffer: &mut [u8]) -> Result<(), Ip2RegionError> {
+    file.seek(SeekFrom::Start(offset as u64)).await?;
+    file.read_exact(buffer).await?;
+    Ok(())
+}
+
+/// the async version of `search_in_reader` without the vector index cache
+async fn search_in_file<I: SegmentIp>(file: &mut File, ip: I) -> Result<String, Ip2RegionError> {
+    let mut vector_index_value = [0; VECTOR_INDEX_SIZE];
+    read_at(
+        file,
+        HEADER_INFO_LENGTH + ip.vector_index_offset(),
+        &mut vector_index_value,
+    )
+    .await?;
+    let start_ptr = ptr_at(&vector_index_value, 0);
+    let end_ptr = ptr_at(&vector_index_value, 4);
+
+    let mut left: usize = 0;
+    let mut right: usize = last_segment::<I>(start_ptr, end_ptr)?;
+    let mut buffer = [0; IPV6_SEGMENT_INDEX_SIZE];
+    let buffer_ip_value = &mut buffer[..I::SEGMENT_INDEX_SIZE];
+
+    while left <= right {
+        let mid = (left + right) >> 1;
+        let offset = start_ptr + mid * I::SEGMENT_INDEX_SIZE;
+        read_at(file, offset, buffer_ip_value).await?;
+        let start_ip = I::from_segment_bytes(buffer_ip_value);
+        if ip < start_ip {
+            // the ip is below the first segment of the block
+            right = match mid.checked_sub(1) {
+                Some(right) => right,
+                None => break,
+            };
+        } else if ip > I::from_segment_bytes(&buffer_ip_value[I::BYTES..]) {
+            left = mid + 1;
+        } else {
+            let data_length = usize::from(u16_at(buffer_ip_value, I::BYTES * 2));
+            let data_offset = ptr_at(buffer_ip_value, I::BYTES * 2 + 2);
+            let mut data = vec![0; data_length];
+            read_at(file, data_offset, &mut data).await?;
+            return Ok(String::from_utf8(data)?);
+        }
+    }
+    Err(Ip2RegionError::NotMatched)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::searcher::default_detect_xdb_file;
+    use crate::{searcher_init, CachePolicy};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_search_async() {
+        let xdb_filepath = default_detect_xdb_file().unwrap();
+        let full = Searcher::new(&xdb_filepath).unwrap();
+        let never = Searcher::with_cache_policy(&xdb_filepath, CachePolicy::Never).unwrap();
+        for ip in (0..u32::MAX).step_by(997_331) {
+            assert_eq!(
+                full.search(ip).unwrap(),
+                never.search_async(ip).await.unwrap()
+            );
+        }
+
+        assert_eq!(
+            full.search_hostname_async("localhost").await.unwrap(),
+            full.search("127.0.0.1").unwrap()
+        );
+
+        searcher_init(None).unwrap();
+        assert_eq!(
+            search_by_ip_async("1.0.1.0").await.unwrap().raw(),
+            "中国|0|福建省|福州市|电信"
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use crate::format::{check_header, search_in_buffer, vector_index_length, SegmentIp};
+use crate::{Ip2RegionError, IpInfo, IpVersion, ToUInt128IP, ToUIntIP};
+
+/// a searcher over a whole xdb file borrowed from the caller, like a `&'static [u8]` of
+/// `include_bytes!` or a memory map owned elsewhere, it searches like `CachePolicy::Full`
+/// without copying the file
+///
+/// ```no_run
+/// use xdb::BorrowedSearcher;
+///
+/// static XDB: &[u8] = include_bytes!("../../../../data/ip2region.xdb");
+///
+/// let searcher = BorrowedSearcher::try_from(XDB).unwrap();
+/// println!("{}", searcher.search("1.0.1.0").unwrap());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct BorrowedSearcher<'a> {
+    buffer: &'a [u8],
+    ip_version: IpVersion,
+    vector_index_length: usize,
+}
+
+impl<'a> TryFrom<&'a [u8]> for BorrowedSearcher<'a> {
+    type Error = Ip2RegionError;
+
+    /// the header is checked like `Searcher::from_bytes`
+    fn try_from(buffer: &'a [u8]) -> Result<Self, Self::Error> {
+        let ip_version = check_header(buffer, buffer.len())?;
+        Ok(Self {
+            buffer,
+            ip_version,
+            vector_index_length: vector_index_length(buffer)?,
+        })
+    }
+}
+
+impl<'a> BorrowedSearcher<'a> {
+    /// the version of the ips stored in the xdb file
+    pub fn ip_version(&self) -> IpVersion {
+        self.ip_version
+    }
+
+    pub fn search<T>(&self, ip: T) -> Result<IpInfo, Ip2RegionError>
+    where
+        T: ToUIntIP,
+    {
+        self.search_info(ip.to_u32_ip()?)
+    }
+
+    /// search an ipv6 address, only works for the dual-stack xdb file built with ipv6 segments
+    pub fn search_by_ipv6<T>(&self, ip: T) -> Result<IpInfo, Ip2RegionError>
+    where
+        T: ToUInt128IP,
+    {
+        self.search_info(ip.to_u128_ip()?)
+    }
+
+    /// the raw region bytes in the borrowed buffer, not checked as utf-8
+    pub fn search_raw(&self, ip: u32) -> Result<&'a [u8], Ip2RegionError> {
+        self.search_region(ip)
+    }
+
+    fn search_info<I: SegmentIp>(&self, ip: I) -> Result<IpInfo, Ip2RegionError> {
+        IpInfo::try_from(String::from_utf8(self.search_region(ip)?.to_vec())?)
+    }
+
+    fn search_region<I: SegmentIp>(&self, ip: I) -> Result<&'a [u8], Ip2RegionError> {
+        if I::VERSION != self.ip_version {
+            return Err(Ip2RegionError::IpVersionMismatch);
+        }
+        Ok(&self.buffer[search_in_buffer(self.buffer, self.vector_index_length, ip)?])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::searcher::default_detect_xdb_file;
+    use crate::Searcher;
+
+    use super::*;
+
+    #[test]
+    fn test_borrowed_searcher() {
+        let buffer = std::fs::read(default_detect_xdb_file().unwrap()).unwrap();
+        let borrowed = BorrowedSearcher::try_from(&buffer[..]).unwrap();
+        let searcher = Searcher::from_bytes(buffer.clone()).unwrap();
+        for ip in (0..u32::MAX).step_by(997_331) {
+            assert_eq!(borrowed.search(ip).unwrap(), searcher.search(ip).unwrap());
+        }
+        assert_eq!(
+            borrowed
+                .search_raw(u32::from_be_bytes([1, 0, 1, 0]))
+                .unwrap(),
+            "中国|0|福建省|福州市|电信".as_bytes()
+        );
+        assert!(matches!(
+            borrowed.search_by_ipv6("2001:db8::1"),
+            Err(Ip2RegionError::IpVersionMismatch)
+        ));
+        assert!(matches!(
+            BorrowedSearcher::try_from(&buffer[..1024]),
+            Err(Ip2RegionError::InvalidDatabase { .. })
+        ));
+    }
+}
@@ -0,0 +1,137 @@
+use std::path::{Path, PathBuf};
+
+use crate::searcher::default_detect_xdb_file;
+use crate::{CachePolicy, Ip2RegionError, Searcher};
+
+/// build a `Searcher` step by step
+///
+/// ```no_run
+/// use xdb::{CachePolicy, SearcherBuilder};
+///
+/// let searcher = SearcherBuilder::new()
+///     .path("/data/ip2region.xdb")
+///     .cache_policy(CachePolicy::VecIndex)
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Default)]
+pub struct SearcherBuilder {
+    source: Option<Source>,
+    cache_policy: CachePolicy,
+}
+
+enum Source {
+    Path(PathBuf),
+    Bytes(Vec<u8>),
+}
+
+impl SearcherBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// build from the xdb file content already in memory, for example embedded by `include_bytes!`,
+    /// the searcher always uses `CachePolicy::Full` as the whole content is already cached, any
+    /// other cache policy is an `UnsupportedPolicy` error of `build`
+    pub fn from_bytes(buffer: Vec<u8>) -> Self {
+        Self {
+            source: Some(Source::Bytes(buffer)),
+            cache_policy: CachePolicy::Full,
+        }
+    }
+
+    /// the xdb filepath, if not set, it will detect the xdb file like `searcher_init(None)`,
+    /// it replaces the content of `from_bytes`
+    pub fn path<P: AsRef<Path>>(mut self, xdb_filepath: P) -> Self {
+        self.source = Some(Source::Path(xdb_filepath.as_ref().to_path_buf()));
+        self
+    }
+
+    /// the cache policy for the xdb file, default is `CachePolicy::Full`
+    pub fn cache_policy(mut self, cache_policy: CachePolicy) -> Self {
+        self.cache_policy = cache_policy;
+        self
+    }
+
+    /// check the xdb file exists and the header is valid, then create the searcher
+    pub fn build(self) -> Result<Searcher, Ip2RegionError> {
+        match self.source {
+            Some(Source::Bytes(_)) if self.cache_policy != CachePolicy::Full => {
+                Err(Ip2RegionError::UnsupportedPolicy(self.cache_policy))
+            }
+            Some(Source::Bytes(buffer)) => Searcher::from_bytes(buffer),
+            Some(Source::Path(xdb_filepath)) => {
+                Searcher::with_cache_policy(xdb_filepath, self.cache_policy)
+            }
+            None => Searcher::with_cache_policy(default_detect_xdb_file()?, self.cache_policy),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_build_with_cache_policy() {
+        for cache_policy in [CachePolicy::Never, CachePolicy::VecIndex, CachePolicy::Full] {
+            let searcher = SearcherBuilder::new()
+                .cache_policy(cache_policy)
+                .build()
+                .unwrap();
+            assert_eq!(searcher.cache_policy(), cache_policy);
+            assert_eq!(
+                searcher.search("1.0.1.0").unwrap().raw(),
+                "中国|0|福建省|福州市|电信"
+            );
+        }
+    }
+
+    #[test]
+    fn test_build_from_bytes() {
+        let buffer = fs::read(default_detect_xdb_file().unwrap()).unwrap();
+        let searcher = SearcherBuilder::from_bytes(buffer.clone()).build().unwrap();
+        assert_eq!(searcher.cache_policy(), CachePolicy::Full);
+        assert_eq!(
+            searcher.search("1.0.1.0").unwrap().raw(),
+            "中国|0|福建省|福州市|电信"
+        );
+
+        for cache_policy in [CachePolicy::Never, CachePolicy::VecIndex] {
+            let result = SearcherBuilder::from_bytes(buffer.clone())
+                .cache_policy(cache_policy)
+                .build();
+            assert!(matches!(
+                result,
+                Err(Ip2RegionError::UnsupportedPolicy(policy)) if policy == cache_policy
+            ));
+        }
+
+        let result = SearcherBuilder::from_bytes(buffer)
+            .path("not_exists.xdb")
+            .build();
+        assert!(matches!(result, Err(Ip2RegionError::FileNotFound(_))));
+    }
+
+    #[test]
+    fn test_build_invalid_xdb() {
+        let result = SearcherBuilder::new().path("not_exists.xdb").build();
+        assert!(matches!(result, Err(Ip2RegionError::FileNotFound(_))));
+
+        let result = SearcherBuilder::from_bytes(vec![0; 1024]).build();
+        assert!(matches!(
+            result,
+            Err(Ip2RegionError::InvalidDatabase { .. })
+        ));
+
+        let mut buffer = fs::read(default_detect_xdb_file().unwrap()).unwrap();
+        buffer[0] = 9;
+        let result = SearcherBuilder::from_bytes(buffer).build();
+        assert!(matches!(
+            result,
+            Err(Ip2RegionError::InvalidDatabase { .. })
+        ));
+    }
+}
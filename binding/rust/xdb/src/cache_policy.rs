@@ -0,0 +1,120 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::Ip2RegionError;
+
+/// how much of the xdb file a `Searcher` keeps in memory
+/// with the `serde` feature it is (de)serialized by the names of `as_str`,
+/// like `cache_policy = "vec_index"` in a config file, and `Segmented` like
+/// `cache_policy = { segmented = 1048576 }`
+///
+/// new policies may be added in a minor version, so a `match` on it needs a `_` arm outside
+/// of this crate, see `CHANGELOG.md`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum CachePolicy {
+    /// cache nothing, the xdb file is opened and read on every search
+    Never,
+    /// only cache the vector index block, the segment index and the region data
+    /// are read from the xdb file on every search
+    VecIndex,
+    /// cache the vector index and the first bytes after it, the region data and the segment
+    /// index of the lowest ips in the files of the official maker, for the memory constrained
+    /// services whose searches are mostly in a small ip space, the other bytes are read from
+    /// the xdb file like `VecIndex`. it is written `segmented:<bytes>` by `Display` and `FromStr`
+    Segmented(usize),
+    /// cache the whole xdb file, this is the fastest policy
+    #[default]
+    Full,
+    /// map the whole xdb file into memory, it is as fast as `Full` once the pages are loaded
+    #[cfg(feature = "memmap2")]
+    Mmap,
+}
+
+impl CachePolicy {
+    /// the canonical name used by `Display` and `FromStr`, without the bytes of `Segmented`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CachePolicy::Never => "never",
+            CachePolicy::VecIndex => "vec_index",
+            CachePolicy::Segmented(_) => "segmented",
+            CachePolicy::Full => "full",
+            #[cfg(feature = "memmap2")]
+            CachePolicy::Mmap => "mmap",
+        }
+    }
+}
+
+impl Display for CachePolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CachePolicy::Segmented(bytes) => write!(f, "{}:{bytes}", self.as_str()),
+            _ => f.write_str(self.as_str()),
+        }
+    }
+}
+
+impl FromStr for CachePolicy {
+    type Err = Ip2RegionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "never" => Ok(CachePolicy::Never),
+            "vec_index" => Ok(CachePolicy::VecIndex),
+            "full" => Ok(CachePolicy::Full),
+            #[cfg(feature = "memmap2")]
+            "mmap" => Ok(CachePolicy::Mmap),
+            _ => s
+                .strip_prefix("segmented:")
+                .and_then(|bytes| bytes.parse().ok())
+                .map(CachePolicy::Segmented)
+                .ok_or_else(|| Ip2RegionError::InvalidCachePolicy(s.to_owned())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        for policy in [CachePolicy::Never, CachePolicy::VecIndex, CachePolicy::Full] {
+            assert_eq!(policy.to_string().parse::<CachePolicy>().unwrap(), policy);
+        }
+        assert_eq!(CachePolicy::VecIndex.to_string(), "vec_index");
+        let segmented = CachePolicy::Segmented(1 << 20);
+        assert_eq!(segmented.to_string(), "segmented:1048576");
+        assert_eq!(
+            segmented.to_string().parse::<CachePolicy>().unwrap(),
+            segmented
+        );
+        for invalid in ["segmented", "segmented:", "segmented:-1"] {
+            assert!(invalid.parse::<CachePolicy>().is_err());
+        }
+        assert!(matches!(
+            "VecIndex".parse::<CachePolicy>(),
+            Err(Ip2RegionError::InvalidCachePolicy(_))
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        for policy in [CachePolicy::Never, CachePolicy::VecIndex, CachePolicy::Full] {
+            let json = serde_json::to_string(&policy).unwrap();
+            assert_eq!(json, format!("\"{policy}\""));
+            assert_eq!(serde_json::from_str::<CachePolicy>(&json).unwrap(), policy);
+        }
+        assert!(serde_json::from_str::<CachePolicy>("\"VecIndex\"").is_err());
+        let segmented = CachePolicy::Segmented(1 << 20);
+        let json = serde_json::to_string(&segmented).unwrap();
+        assert_eq!(json, r#"{"segmented":1048576}"#);
+        assert_eq!(
+            serde_json::from_str::<CachePolicy>(&json).unwrap(),
+            segmented
+        );
+    }
+}
@@ -0,0 +1,95 @@
+//! a lru cache of the search results over a `Searcher`, for the workloads where a few ips
+//! take most of the searches, like the access logs of a site
+use std::num::NonZeroUsize;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use lru::LruCache;
+
+use crate::{Ip2RegionError, IpInfo, Searcher, ToUIntIP};
+
+/// a `Searcher` remembering the results of the latest `capacity` distinct ips,
+/// the missed ips are searched by the inner searcher and only the found ones are cached
+pub struct CachedSearcher {
+    searcher: Searcher,
+    cache: Mutex<LruCache<u32, IpInfo>>,
+}
+
+impl CachedSearcher {
+    /// cache at most `capacity` ips in front of `searcher`, panics if `capacity` is 0
+    pub fn new(searcher: Searcher, capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).expect("the lru capacity must not be 0");
+        Self {
+            searcher,
+            cache: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// same as `Searcher::search`, the result of a cached ip is cloned out of the cache
+    pub fn search<T>(&self, ip: T) -> Result<IpInfo, Ip2RegionError>
+    where
+        T: ToUIntIP,
+    {
+        let ip = ip.to_u32_ip()?;
+        if let Some(info) = self.lock_cache().get(&ip) {
+            return Ok(info.clone());
+        }
+        // the lock is not held while searching, two threads may search the same ip at once
+        let info = self.searcher.search(ip)?;
+        self.lock_cache().put(ip, info.clone());
+        Ok(info)
+    }
+
+    /// the inner searcher, call `clear` after reloading it or the old results are kept
+    pub fn searcher(&self) -> &Searcher {
+        &self.searcher
+    }
+
+    /// drop all the cached results
+    pub fn clear(&self) {
+        self.lock_cache().clear();
+    }
+
+    /// the cache only holds the complete results, so it is still valid after a panic
+    fn lock_cache(&self) -> MutexGuard<'_, LruCache<u32, IpInfo>> {
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::searcher::default_detect_xdb_file;
+
+    use super::*;
+
+    #[test]
+    fn test_cached_search() {
+        let xdb_filepath = default_detect_xdb_file().unwrap();
+        let searcher = Searcher::new(&xdb_filepath).unwrap();
+        let cached = CachedSearcher::new(Searcher::new(&xdb_filepath).unwrap(), 2);
+        for ip in [
+            "1.0.1.0",
+            "2.0.0.0",
+            "1.0.1.0",
+            "255.255.255.255",
+            "2.0.0.0",
+        ] {
+            assert_eq!(cached.search(ip).unwrap(), searcher.search(ip).unwrap());
+        }
+        assert_eq!(cached.lock_cache().len(), 2);
+        assert!(!cached
+            .lock_cache()
+            .contains(&u32::from_be_bytes([1, 0, 1, 0])));
+
+        cached.clear();
+        assert!(cached.lock_cache().is_empty());
+        assert!(cached.search("256.0.0.0").is_err());
+        assert!(cached.lock_cache().is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_capacity() {
+        let searcher = Searcher::new(default_detect_xdb_file().unwrap()).unwrap();
+        CachedSearcher::new(searcher, 0);
+    }
+}
@@ -0,0 +1,119 @@
+//! an optional crc32 of the xdb file stored in the unused tail of its header, the xdb format
+//! has no checksum of its own so this is an extension of the format:
+//!
+//! | header offset | length | value                                                     |
+//! |---------------|--------|-----------------------------------------------------------|
+//! | 248           | 4      | the magic `xcrc`                                          |
+//! | 252           | 4      | little-endian crc32 (ieee) of every byte after the header |
+//!
+//! the other readers of the xdb format ignore these bytes, so a file stamped by
+//! `build_verified_xdb` is still searched by every binding
+use alloc::borrow::ToOwned;
+use alloc::format;
+
+use crate::format::{check_header, u32_at, HEADER_INFO_LENGTH};
+use crate::Ip2RegionError;
+
+const CHECKSUM_MAGIC_OFFSET: usize = 248;
+const CHECKSUM_MAGIC: [u8; 4] = *b"xcrc";
+const CHECKSUM_OFFSET: usize = 252;
+
+/// stamp the crc32 of the data behind the header into the header of the whole xdb file `xdb`,
+/// the file must be valid, a previous checksum is replaced
+pub fn build_verified_xdb(xdb: &mut [u8]) -> Result<(), Ip2RegionError> {
+    check_header(xdb, xdb.len())?;
+    let checksum = Crc32::new().update(&xdb[HEADER_INFO_LENGTH..]).finish();
+    xdb[CHECKSUM_MAGIC_OFFSET..CHECKSUM_OFFSET].copy_from_slice(&CHECKSUM_MAGIC);
+    xdb[CHECKSUM_OFFSET..HEADER_INFO_LENGTH].copy_from_slice(&checksum.to_le_bytes());
+    Ok(())
+}
+
+/// check the checksum stamped by `build_verified_xdb` into the whole xdb file `xdb`,
+/// the same as `Searcher::verify_integrity` without `std`
+pub fn verify_xdb_checksum(xdb: &[u8]) -> Result<(), Ip2RegionError> {
+    check_header(xdb, xdb.len())?;
+    let expected = stored_checksum(xdb)?;
+    check_checksum(
+        expected,
+        Crc32::new().update(&xdb[HEADER_INFO_LENGTH..]).finish(),
+    )
+}
+
+/// the checksum stamped by `build_verified_xdb` into the checked `header`
+pub(crate) fn stored_checksum(header: &[u8]) -> Result<u32, Ip2RegionError> {
+    if header[CHECKSUM_MAGIC_OFFSET..CHECKSUM_OFFSET] != CHECKSUM_MAGIC {
+        return Err(Ip2RegionError::InvalidDatabase {
+            reason: "the xdb file has no checksum, stamp it by build_verified_xdb".to_owned(),
+        });
+    }
+    Ok(u32_at(header, CHECKSUM_OFFSET))
+}
+
+/// compare the checksum computed over the data behind the header to the stored one
+pub(crate) fn check_checksum(expected: u32, actual: u32) -> Result<(), Ip2RegionError> {
+    if expected != actual {
+        return Err(Ip2RegionError::InvalidDatabase {
+            reason: format!(
+                "checksum mismatch, stored {expected:#010x} but computed {actual:#010x}"
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// the reflected crc32 of the ieee polynomial, the one of zip and png
+pub(crate) struct Crc32(u32);
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut crc = index as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[index] = crc;
+        index += 1;
+    }
+    table
+}
+
+impl Crc32 {
+    pub(crate) fn new() -> Self {
+        Self(u32::MAX)
+    }
+
+    pub(crate) fn update(mut self, bytes: &[u8]) -> Self {
+        for &byte in bytes {
+            self.0 = CRC32_TABLE[((self.0 ^ u32::from(byte)) & 0xff) as usize] ^ (self.0 >> 8);
+        }
+        self
+    }
+
+    pub(crate) fn finish(self) -> u32 {
+        !self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(Crc32::new().finish(), 0);
+        assert_eq!(Crc32::new().update(b"123456789").finish(), 0xcbf4_3926);
+        assert_eq!(
+            Crc32::new().update(b"1234").update(b"56789").finish(),
+            0xcbf4_3926
+        );
+    }
+}
@@ -0,0 +1,29 @@
+//! the xdb file compiled into the binary by the `embed` feature, for the single file cli tools
+//! and edge functions, the file is `XDB_EMBED_PATH` at build time or the data/ip2region.xdb of
+//! this repository, it is checked by the build script
+use crate::Searcher;
+
+/// the whole xdb file embedded at build time
+pub static EMBEDDED_XDB: &[u8] = include_bytes!(env!("XDB_EMBED_FILE"));
+
+/// a `CachePolicy::Full` searcher over a copy of `EMBEDDED_XDB`, instead of the xdb file
+/// of `XDB_FILEPATH` without the `embed` feature
+impl Default for Searcher {
+    fn default() -> Self {
+        Searcher::from_bytes(EMBEDDED_XDB.to_vec()).expect("the embedded xdb file is checked")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedded_searcher() {
+        assert!(!EMBEDDED_XDB.is_empty());
+        assert_eq!(
+            Searcher::default().search("1.0.1.0").unwrap().raw(),
+            "中国|0|福建省|福州市|电信"
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use alloc::string::{FromUtf8Error, String};
+#[cfg(not(feature = "std"))]
+use core::fmt::{Display, Formatter};
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::path::PathBuf;
+
+#[cfg(feature = "std")]
+use crate::CachePolicy;
+
+/// all the errors returned by the xdb searcher
+///
+/// `Display`, `Error` and the `From` of the wrapped errors are derived by `thiserror` with
+/// `std`, it needs `std` below rust 1.81, so `no_std` keeps a plain `Display` of its variants
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+pub enum Ip2RegionError {
+    /// the xdb file does not exist
+    #[cfg(feature = "std")]
+    #[cfg_attr(feature = "std", error("xdb file not found at {}", .0.display()))]
+    FileNotFound(PathBuf),
+    /// the buffer is not a valid xdb database
+    #[cfg_attr(feature = "std", error("invalid xdb database: {reason}"))]
+    InvalidDatabase { reason: String },
+    /// the input can not be converted to an ip
+    #[cfg_attr(feature = "std", error("invalid ip: {0}"))]
+    InvalidIp(String),
+    /// the ip is not covered by any segment of the xdb database
+    #[cfg_attr(feature = "std", error("not matched"))]
+    NotMatched,
+    /// search an ipv4 address in an ipv6 xdb database, or the other way around
+    #[cfg_attr(
+        feature = "std",
+        error("the ip version does not match the xdb database")
+    )]
+    IpVersionMismatch,
+    /// the input is not a region in the `country|region|province|city|isp` format
+    #[cfg(feature = "std")]
+    #[cfg_attr(feature = "std", error("invalid region: {0}"))]
+    ParseError(String),
+    /// the input is not one of the `CachePolicy` names
+    #[cfg(feature = "std")]
+    #[cfg_attr(feature = "std", error("invalid cache policy: {0}"))]
+    InvalidCachePolicy(String),
+    /// the operation is not supported by the cache policy of the searcher
+    #[cfg(feature = "std")]
+    #[cfg_attr(feature = "std", error("not supported by the {0} cache policy"))]
+    UnsupportedPolicy(CachePolicy),
+    /// the region data is not valid utf-8
+    #[cfg_attr(feature = "std", error("invalid utf-8 region data"))]
+    InvalidUtf8(#[cfg_attr(feature = "std", from)] FromUtf8Error),
+    /// any other io error while reading the xdb file
+    #[cfg(feature = "std")]
+    #[cfg_attr(feature = "std", error("io error"))]
+    Io(#[cfg_attr(feature = "std", from)] io::Error),
+    /// the xdb file can not be downloaded by `Searcher::from_url`
+    #[cfg(feature = "remote")]
+    #[cfg_attr(feature = "std", error("http error"))]
+    Http(#[cfg_attr(feature = "std", from)] reqwest::Error),
+    /// the xdb file can not be watched by `Searcher::watch_file`
+    #[cfg(feature = "watch")]
+    #[cfg_attr(feature = "std", error("watch error"))]
+    Watch(#[cfg_attr(feature = "std", from)] notify::Error),
+}
+
+/// the same messages as the `thiserror` ones, except the cause of `InvalidUtf8` is written
+/// inline as there is no `source` to report it without `std`
+#[cfg(not(feature = "std"))]
+impl Display for Ip2RegionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Ip2RegionError::InvalidDatabase { reason } => {
+                write!(f, "invalid xdb database: {reason}")
+            }
+            Ip2RegionError::InvalidIp(ip) => write!(f, "invalid ip: {ip}"),
+            Ip2RegionError::NotMatched => write!(f, "not matched"),
+            Ip2RegionError::IpVersionMismatch => {
+                write!(f, "the ip version does not match the xdb database")
+            }
+            Ip2RegionError::InvalidUtf8(e) => write!(f, "invalid utf-8 region data: {e}"),
+        }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl From<FromUtf8Error> for Ip2RegionError {
+    fn from(e: FromUtf8Error) -> Self {
+        Ip2RegionError::InvalidUtf8(e)
+    }
+}
@@ -0,0 +1,147 @@
+//! dump all the segments of an ipv4 xdb file, for migrating them into the other databases
+use std::io::{BufWriter, Write};
+use std::net::Ipv4Addr;
+
+use crate::{Ip2RegionError, Searcher};
+
+const CSV_HEADER: [&str; 7] = [
+    "start_ip", "end_ip", "country", "region", "province", "city", "isp",
+];
+
+impl Searcher {
+    /// write all the segments to `writer` as a csv of rfc 4180 with a header row and the
+    /// columns `start_ip,end_ip,country,region,province,city,isp`, the ips are in the
+    /// dotted-decimal form, returns the number of the segment rows.
+    ///
+    /// `writer` is buffered internally, only ipv4 xdb files are supported like `iter_segments`
+    pub fn export_csv<W: Write>(&self, writer: W) -> Result<u64, Ip2RegionError> {
+        let mut writer = BufWriter::new(writer);
+        write_csv_row(&mut writer, CSV_HEADER)?;
+        let mut rows = 0;
+        for segment in self.iter_segments() {
+            let segment = segment?;
+            let (start_ip, end_ip) = (
+                Ipv4Addr::from(segment.start_ip).to_string(),
+                Ipv4Addr::from(segment.end_ip).to_string(),
+            );
+            let info = &segment.info;
+            write_csv_row(
+                &mut writer,
+                [
+                    &start_ip,
+                    &end_ip,
+                    &info.country,
+                    &info.region,
+                    &info.province,
+                    &info.city,
+                    &info.isp,
+                ],
+            )?;
+            rows += 1;
+        }
+        writer.flush()?;
+        Ok(rows)
+    }
+
+    /// write all the segments to `writer` as a json array of `Segment`, streamed segment by
+    /// segment so the whole array is never in memory, the ips are the u32 numbers,
+    /// `writer` is buffered internally
+    #[cfg(feature = "serde")]
+    pub fn export_json<W: Write>(&self, writer: W) -> Result<(), Ip2RegionError> {
+        use serde::ser::{SerializeSeq, Serializer};
+
+        let mut serializer = serde_json::Serializer::new(BufWriter::new(writer));
+        let mut seq = serializer
+            .serialize_seq(None)
+            .map_err(std::io::Error::from)?;
+        for segment in self.iter_segments() {
+            seq.serialize_element(&segment?)
+                .map_err(std::io::Error::from)?;
+        }
+        seq.end().map_err(std::io::Error::from)?;
+        serializer.into_inner().flush()?;
+        Ok(())
+    }
+}
+
+/// a row of fields ended by a crlf, the fields with a comma, a quote or a line break are quoted
+fn write_csv_row<W: Write, S: AsRef<str>>(
+    writer: &mut W,
+    fields: [S; 7],
+) -> Result<(), Ip2RegionError> {
+    for (index, field) in fields.iter().enumerate() {
+        if index > 0 {
+            writer.write_all(b",")?;
+        }
+        let field = field.as_ref();
+        if field.contains([',', '"', '\r', '\n']) {
+            write!(writer, "\"{}\"", field.replace('"', "\"\""))?;
+        } else {
+            writer.write_all(field.as_bytes())?;
+        }
+    }
+    writer.write_all(b"\r\n")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::searcher::default_detect_xdb_file;
+
+    use super::*;
+
+    #[test]
+    fn test_write_csv_row() {
+        let mut row = Vec::new();
+        write_csv_row(&mut row, ["a", "b,c", "d\"e", "f\ng", "", "中国", "0"]).unwrap();
+        assert_eq!(
+            String::from_utf8(row).unwrap(),
+            "a,\"b,c\",\"d\"\"e\",\"f\ng\",,中国,0\r\n"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_export_json() {
+        let segments = [
+            (0u32, 0x00FF_FFFF, "0|0|0|内网IP|内网IP"),
+            (0x0100_0000, u32::MAX, "美国|0|0|0|0"),
+        ];
+        let searcher = Searcher::from_bytes(crate::maker::make_xdb(&segments)).unwrap();
+        let mut json = Vec::new();
+        searcher.export_json(&mut json).unwrap();
+        let exported: Vec<crate::Segment> = serde_json::from_slice(&json).unwrap();
+        let expected: Vec<_> = searcher.iter_segments().map(Result::unwrap).collect();
+        assert_eq!(exported, expected);
+        assert!(json.starts_with(
+            r#"[{"start_ip":0,"end_ip":65535,"info":{"country":"0","region":"0","province":"0","city":"内网IP","isp":"内网IP"}},"#
+                .as_bytes()
+        ));
+    }
+
+    #[test]
+    fn test_export_csv() {
+        let segments = [
+            (0u32, 0x0A00_00FF, "0|0|0|内网IP|内网IP"),
+            (0x0A00_0100, u32::MAX, "美国|0|加利福尼亚, 洛杉矶|0|0"),
+        ];
+        let searcher = Searcher::from_bytes(crate::maker::make_xdb(&segments)).unwrap();
+        let mut csv = Vec::new();
+        // the maker splits the segments at the vector index cells
+        assert_eq!(searcher.export_csv(&mut csv).unwrap(), 256 * 256 + 1);
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.starts_with(
+            "start_ip,end_ip,country,region,province,city,isp\r\n\
+             0.0.0.0,0.0.255.255,0,0,0,内网IP,内网IP\r\n"
+        ));
+        assert!(csv.contains("\r\n10.0.1.0,10.0.255.255,美国,0,\"加利福尼亚, 洛杉矶\",0,0\r\n"));
+
+        let searcher = Searcher::new(default_detect_xdb_file().unwrap()).unwrap();
+        let mut csv = Vec::new();
+        let rows = searcher.export_csv(&mut csv).unwrap();
+        assert_eq!(rows, searcher.segment_count() as u64);
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count() as u64, rows + 1);
+        assert!(csv.contains("\r\n1.0.1.0,1.0.3.255,中国,0,福建省,福州市,电信\r\n"));
+    }
+}
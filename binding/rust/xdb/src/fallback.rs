@@ -0,0 +1,91 @@
+//! chain two ip databases, for example ip2region first and a maxmind geolite2 database
+//! wrapped in a `Search` implementation for the ips ip2region does not know
+use crate::{Ip2RegionError, IpInfo, Searcher};
+
+/// anything searching the region of an ipv4 address
+pub trait Search {
+    fn search(&self, ip: u32) -> Result<IpInfo, Ip2RegionError>;
+}
+
+impl Search for Searcher {
+    fn search(&self, ip: u32) -> Result<IpInfo, Ip2RegionError> {
+        Searcher::search(self, ip)
+    }
+}
+
+#[cfg(feature = "lru")]
+impl Search for crate::CachedSearcher {
+    fn search(&self, ip: u32) -> Result<IpInfo, Ip2RegionError> {
+        crate::CachedSearcher::search(self, ip)
+    }
+}
+
+/// search `primary` first and `fallback` only when the primary returns
+/// `Ip2RegionError::NotMatched`, the other errors of the primary are returned as they are
+pub struct FallbackSearcher<P, F> {
+    primary: P,
+    fallback: F,
+}
+
+impl<P: Search, F: Search> FallbackSearcher<P, F> {
+    pub fn new(primary: P, fallback: F) -> Self {
+        Self { primary, fallback }
+    }
+
+    pub fn primary(&self) -> &P {
+        &self.primary
+    }
+
+    pub fn fallback(&self) -> &F {
+        &self.fallback
+    }
+}
+
+impl<P: Search, F: Search> Search for FallbackSearcher<P, F> {
+    fn search(&self, ip: u32) -> Result<IpInfo, Ip2RegionError> {
+        match self.primary.search(ip) {
+            Err(Ip2RegionError::NotMatched) => self.fallback.search(ip),
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::searcher::default_detect_xdb_file;
+
+    use super::*;
+
+    /// knows nothing but the ips below 1.0.0.0, which are invalid
+    struct Partial;
+
+    impl Search for Partial {
+        fn search(&self, ip: u32) -> Result<IpInfo, Ip2RegionError> {
+            if ip < 1 << 24 {
+                return Err(Ip2RegionError::InvalidIp(ip.to_string()));
+            }
+            Err(Ip2RegionError::NotMatched)
+        }
+    }
+
+    #[test]
+    fn test_fallback_on_not_matched() {
+        let searcher = Searcher::new(default_detect_xdb_file().unwrap()).unwrap();
+        let expected = Searcher::search(&searcher, "1.0.1.0").unwrap();
+        let fallback = FallbackSearcher::new(Partial, searcher);
+        assert_eq!(
+            fallback.search(u32::from_be_bytes([1, 0, 1, 0])).unwrap(),
+            expected
+        );
+        assert!(matches!(
+            fallback.search(1),
+            Err(Ip2RegionError::InvalidIp(_))
+        ));
+
+        let both = FallbackSearcher::new(Partial, Partial);
+        assert!(matches!(
+            both.search(u32::MAX),
+            Err(Ip2RegionError::NotMatched)
+        ));
+    }
+}
@@ -0,0 +1,502 @@
+//! the xdb file format and the search over a buffer holding the whole file, this part only
+//! needs `core` and `alloc` so it builds without the `std` feature
+use alloc::borrow::ToOwned;
+use alloc::format;
+use core::ops::Range;
+
+use crate::Ip2RegionError;
+
+/// the bytes of the header at the start of every xdb file, see `DatabaseInfo` for its fields,
+/// the vector index follows it
+pub const HEADER_INFO_LENGTH: usize = 256;
+/// the rows and the columns of the vector index, a row per first byte and a column per second
+/// byte of an ip
+pub const VECTOR_INDEX_COLS: usize = 256;
+/// the bytes of a vector index entry, the little-endian u32 file pointers of the first and
+/// one past the last segment index entries of the ips sharing the first two bytes
+pub const VECTOR_INDEX_SIZE: usize = 8;
+/// the bytes of the vector index, `VECTOR_INDEX_COLS * VECTOR_INDEX_COLS * VECTOR_INDEX_SIZE`,
+/// 512 KiB right after the header
+pub const VECTOR_INDEX_LENGTH: usize = VECTOR_INDEX_COLS * VECTOR_INDEX_COLS * VECTOR_INDEX_SIZE;
+/// an ipv4 segment index entry, every number is little-endian:
+///
+/// | offset | length | field         | value                                     |
+/// |--------|--------|---------------|-------------------------------------------|
+/// | 0      | 4      | `start_ip`    | the first ip of the segment               |
+/// | 4      | 4      | `end_ip`      | the last ip of the segment, inclusive     |
+/// | 8      | 2      | `data_length` | the byte length of the region data        |
+/// | 10     | 4      | `data_offset` | the file pointer of the utf-8 region data |
+///
+/// the entry of `1.0.1.0..=1.0.3.255` with the 35 bytes region `中国|0|福建省|福州市|电信`
+/// at `0x000c2a50`:
+///
+/// ```
+/// let entry: [u8; 14] = [
+///     0x00, 0x01, 0x00, 0x01, // start_ip 1.0.1.0
+///     0xff, 0x03, 0x00, 0x01, // end_ip 1.0.3.255
+///     0x23, 0x00, // data_length 35
+///     0x50, 0x2a, 0x0c, 0x00, // data_offset 0x000c2a50
+/// ];
+/// let (start_ip, rest) = entry.split_at(4);
+/// let (end_ip, rest) = rest.split_at(4);
+/// let (data_length, data_offset) = rest.split_at(2);
+/// assert_eq!(start_ip.len() + end_ip.len() + data_length.len() + data_offset.len(), 14);
+///
+/// let start_ip = u32::from_le_bytes(start_ip.try_into().unwrap());
+/// let end_ip = u32::from_le_bytes(end_ip.try_into().unwrap());
+/// assert_eq!(std::net::Ipv4Addr::from(start_ip).to_string(), "1.0.1.0");
+/// assert_eq!(std::net::Ipv4Addr::from(end_ip).to_string(), "1.0.3.255");
+/// assert_eq!(u16::from_le_bytes(data_length.try_into().unwrap()), 35);
+/// assert_eq!("中国|0|福建省|福州市|电信".len(), 35);
+/// assert_eq!(u32::from_le_bytes(data_offset.try_into().unwrap()), 0x000c_2a50);
+/// ```
+///
+/// the ipv6 entry of `IPV6_SEGMENT_INDEX_SIZE` has the same fields with the 16 bytes ips in
+/// the big-endian order of `Ipv6Addr::octets`
+pub const SEGMENT_INDEX_SIZE: usize = 14;
+const _: () = assert!(4 + 4 + 2 + 4 == SEGMENT_INDEX_SIZE);
+/// the bytes of an ipv6 segment index entry of the dual-stack xdb files, the 16 bytes
+/// big-endian start and end ips, the u16 data length and the u32 data offset
+pub const IPV6_SEGMENT_INDEX_SIZE: usize = 38;
+const _: () = assert!(16 + 16 + 2 + 4 == IPV6_SEGMENT_INDEX_SIZE);
+/// the ipv4 only xdb structure
+pub(crate) const XDB_STRUCTURE_20: u16 = 2;
+/// the dual-stack xdb structure, the ip version is stored at offset 16 of the header
+pub(crate) const XDB_STRUCTURE_30: u16 = 3;
+/// the only index policy of the xdb format in use
+pub(crate) const VECTOR_INDEX_POLICY: u16 = 1;
+
+/// the version of the ips stored in a xdb file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpVersion {
+    V4,
+    V6,
+}
+
+pub(crate) fn too_short() -> Ip2RegionError {
+    Ip2RegionError::InvalidDatabase {
+        reason: "the xdb file is too short to hold the header and the vector index".to_owned(),
+    }
+}
+
+/// a pointer of the vector index or the segment index out of the xdb file
+pub(crate) fn corrupted() -> Ip2RegionError {
+    Ip2RegionError::InvalidDatabase {
+        reason: "the xdb file is corrupted, an index points out of the file".to_owned(),
+    }
+}
+
+/// check the 256 bytes header of a xdb file, the version field is the only magic of the format,
+/// the index pointers must point behind the vector index
+pub fn validate_header(buf: &[u8]) -> Result<(), Ip2RegionError> {
+    parse_header(buf).map(|_| ())
+}
+
+fn parse_header(header: &[u8]) -> Result<IpVersion, Ip2RegionError> {
+    if header.len() < HEADER_INFO_LENGTH {
+        return Err(too_short());
+    }
+    let ip_version = match u16_at(header, 0) {
+        XDB_STRUCTURE_20 => IpVersion::V4,
+        XDB_STRUCTURE_30 => match u16_at(header, 16) {
+            4 => IpVersion::V4,
+            6 => IpVersion::V6,
+            ip_version => {
+                return Err(Ip2RegionError::InvalidDatabase {
+                    reason: format!("unsupported ip version {ip_version}"),
+                })
+            }
+        },
+        version => {
+            return Err(Ip2RegionError::InvalidDatabase {
+                reason: format!(
+                    "unsupported xdb version {version}, expect {XDB_STRUCTURE_20} or {XDB_STRUCTURE_30}"
+                ),
+            })
+        }
+    };
+    let vector_index_length = vector_index_length(header)?;
+    let (start_index_ptr, end_index_ptr) = index_ptrs(header);
+    if start_index_ptr < HEADER_INFO_LENGTH + vector_index_length || end_index_ptr < start_index_ptr
+    {
+        return Err(Ip2RegionError::InvalidDatabase {
+            reason: format!(
+                "invalid segment index range {start_index_ptr}..={end_index_ptr} in the header"
+            ),
+        });
+    }
+    Ok(ip_version)
+}
+
+/// the pointers to the first and the last segment index entries
+pub(crate) fn index_ptrs(header: &[u8]) -> (usize, usize) {
+    (ptr_at(header, 8), ptr_at(header, 12))
+}
+
+/// the length of the vector index block behind the `header`, the header does not store the
+/// dimensions of the vector index, they are fixed by its index policy. it is read once when
+/// the header is checked and kept by the searchers
+pub(crate) fn vector_index_length(header: &[u8]) -> Result<usize, Ip2RegionError> {
+    match u16_at(header, 2) {
+        VECTOR_INDEX_POLICY => Ok(VECTOR_INDEX_LENGTH),
+        index_policy => Err(Ip2RegionError::InvalidDatabase {
+            reason: format!(
+                "unsupported index policy {index_policy}, expect {VECTOR_INDEX_POLICY}"
+            ),
+        }),
+    }
+}
+
+/// check the header before any search, the segment index must be inside the xdb file
+pub(crate) fn check_header(header: &[u8], xdb_length: usize) -> Result<IpVersion, Ip2RegionError> {
+    let ip_version = parse_header(header)?;
+    let segment_index_size = match ip_version {
+        IpVersion::V4 => SEGMENT_INDEX_SIZE,
+        IpVersion::V6 => IPV6_SEGMENT_INDEX_SIZE,
+    };
+    let (_, end_index_ptr) = index_ptrs(header);
+    if end_index_ptr + segment_index_size > xdb_length {
+        return Err(Ip2RegionError::InvalidDatabase {
+            reason: format!(
+                "the xdb file of {xdb_length} bytes is truncated, the segment index ends at {}",
+                end_index_ptr + segment_index_size
+            ),
+        });
+    }
+    Ok(ip_version)
+}
+
+/// the ips stored in the segment index: ipv4 as 4 bytes little endian u32,
+/// ipv6 as 16 bytes big endian u128, both indexed by their leading two bytes in the vector index
+pub(crate) trait SegmentIp: Copy + PartialOrd {
+    const VERSION: IpVersion;
+    const BYTES: usize;
+    /// start ip, end ip, 2 bytes data length and 4 bytes data offset
+    const SEGMENT_INDEX_SIZE: usize;
+
+    /// the offset of the vector index entry for the ip, relative to the vector index block
+    fn vector_index_offset(self) -> usize;
+
+    fn from_segment_bytes(bytes: &[u8]) -> Self;
+}
+
+impl SegmentIp for u32 {
+    const VERSION: IpVersion = IpVersion::V4;
+    const BYTES: usize = 4;
+    const SEGMENT_INDEX_SIZE: usize = SEGMENT_INDEX_SIZE;
+
+    #[inline]
+    fn vector_index_offset(self) -> usize {
+        let il0 = ((self >> 24) & 0xFF) as usize;
+        let il1 = ((self >> 16) & 0xFF) as usize;
+        VECTOR_INDEX_SIZE * (il0 * VECTOR_INDEX_COLS + il1)
+    }
+
+    #[inline]
+    fn from_segment_bytes(bytes: &[u8]) -> Self {
+        u32_at(bytes, 0)
+    }
+}
+
+impl SegmentIp for u128 {
+    const VERSION: IpVersion = IpVersion::V6;
+    const BYTES: usize = 16;
+    const SEGMENT_INDEX_SIZE: usize = IPV6_SEGMENT_INDEX_SIZE;
+
+    #[inline]
+    fn vector_index_offset(self) -> usize {
+        let il0 = ((self >> 120) & 0xFF) as usize;
+        let il1 = ((self >> 112) & 0xFF) as usize;
+        VECTOR_INDEX_SIZE * (il0 * VECTOR_INDEX_COLS + il1)
+    }
+
+    #[inline]
+    fn from_segment_bytes(bytes: &[u8]) -> Self {
+        let mut ip = [0; 16];
+        ip.copy_from_slice(&bytes[..16]);
+        u128::from_be_bytes(ip)
+    }
+}
+
+/// the start and end pointers of the segment index block for the ip
+#[inline]
+pub(crate) fn get_start_end_ptr<I: SegmentIp>(vector_index: &[u8], ip: I) -> (usize, usize) {
+    let idx = ip.vector_index_offset();
+    let start_ptr = ptr_at(vector_index, idx);
+    let end_ptr = ptr_at(vector_index, idx + 4);
+    (start_ptr, end_ptr)
+}
+
+/// how the latest binary search on this thread went, for `SearchStats`
+#[cfg(feature = "stats")]
+#[derive(Clone, Copy)]
+pub(crate) struct LastSearch {
+    pub(crate) iterations: usize,
+    /// the segment index block of the vector index entry
+    pub(crate) start_ptr: usize,
+    pub(crate) end_ptr: usize,
+    /// the pointer of the matched segment index entry, 0 if none matched
+    pub(crate) matched_ptr: usize,
+}
+
+#[cfg(feature = "stats")]
+std::thread_local! {
+    static LAST_SEARCH: core::cell::Cell<LastSearch> = const {
+        core::cell::Cell::new(LastSearch {
+            iterations: 0,
+            start_ptr: 0,
+            end_ptr: 0,
+            matched_ptr: 0,
+        })
+    };
+}
+
+/// the latest binary search on this thread
+#[cfg(feature = "stats")]
+pub(crate) fn last_search() -> LastSearch {
+    LAST_SEARCH.with(core::cell::Cell::get)
+}
+
+/// the index of the last segment index entry between the pointers of a vector index entry,
+/// the end pointer is one past the last entry, an empty block is not matched
+#[inline]
+pub(crate) fn last_segment<I: SegmentIp>(
+    start_ptr: usize,
+    end_ptr: usize,
+) -> Result<usize, Ip2RegionError> {
+    let length = end_ptr.checked_sub(start_ptr).ok_or_else(corrupted)?;
+    (length / I::SEGMENT_INDEX_SIZE)
+        .checked_sub(1)
+        .ok_or(Ip2RegionError::NotMatched)
+}
+
+/// the `binary_search` tracing span around the segment index loop, a no-op without `std`
+pub(crate) struct BinarySearchSpan {
+    #[cfg(feature = "std")]
+    span: tracing::span::EnteredSpan,
+    #[cfg(feature = "stats")]
+    ptrs: (usize, usize),
+}
+
+impl BinarySearchSpan {
+    /// enter the span of searching the segment index block `start_ptr..end_ptr`
+    #[inline]
+    pub(crate) fn enter(start_ptr: usize, end_ptr: usize) -> Self {
+        #[cfg(not(feature = "stats"))]
+        let _ = (start_ptr, end_ptr);
+        Self {
+            #[cfg(feature = "std")]
+            span: tracing::debug_span!("binary_search", iterations = tracing::field::Empty)
+                .entered(),
+            #[cfg(feature = "stats")]
+            ptrs: (start_ptr, end_ptr),
+        }
+    }
+
+    /// record the number of loop iterations and the pointer of the matched segment index
+    /// entry, then exit the span
+    #[inline]
+    pub(crate) fn exit(self, iterations: usize, matched_ptr: Option<usize>) {
+        #[cfg(feature = "stats")]
+        LAST_SEARCH.with(|last| {
+            last.set(LastSearch {
+                iterations,
+                start_ptr: self.ptrs.0,
+                end_ptr: self.ptrs.1,
+                matched_ptr: matched_ptr.unwrap_or(0),
+            })
+        });
+        #[cfg(not(feature = "stats"))]
+        let _ = matched_ptr;
+        #[cfg(feature = "std")]
+        self.span.record("iterations", iterations);
+        #[cfg(not(feature = "std"))]
+        let _ = iterations;
+    }
+}
+
+/// the range of the region data in `buffer`, `vector_index_length` is the one of its header
+#[inline]
+pub(crate) fn search_in_buffer<I: SegmentIp>(
+    buffer: &[u8],
+    vector_index_length: usize,
+    ip: I,
+) -> Result<Range<usize>, Ip2RegionError> {
+    let vector_index_end = HEADER_INFO_LENGTH + vector_index_length;
+    let vector_index = &buffer[HEADER_INFO_LENGTH..vector_index_end];
+    let (start_ptr, end_ptr) = get_start_end_ptr(vector_index, ip);
+    let mut left: usize = 0;
+    let mut right: usize = last_segment::<I>(start_ptr, end_ptr)?;
+
+    let span = BinarySearchSpan::enter(start_ptr, end_ptr);
+    let mut iterations = 0;
+    let mut matched_ptr = None;
+    let mut found = Err(Ip2RegionError::NotMatched);
+    while left <= right {
+        iterations += 1;
+        let mid = (left + right) >> 1;
+        let entry = mid
+            .checked_mul(I::SEGMENT_INDEX_SIZE)
+            .and_then(|length| start_ptr.checked_add(length))
+            .and_then(|offset| Some((offset, bytes_at(buffer, offset, I::SEGMENT_INDEX_SIZE)?)));
+        let (offset, buffer_ip_value) = match entry {
+            Some(entry) => entry,
+            None => {
+                found = Err(corrupted());
+                break;
+            }
+        };
+        let start_ip = I::from_segment_bytes(buffer_ip_value);
+        if ip < start_ip {
+            // the ip is below the first segment of the block
+            right = match mid.checked_sub(1) {
+                Some(right) => right,
+                None => break,
+            };
+        } else if ip > I::from_segment_bytes(&buffer_ip_value[I::BYTES..]) {
+            left = mid + 1;
+        } else {
+            let data_length = usize::from(u16_at(buffer_ip_value, I::BYTES * 2));
+            let data_offset = ptr_at(buffer_ip_value, I::BYTES * 2 + 2);
+            found = match bytes_at(buffer, data_offset, data_length) {
+                Some(_) => Ok(data_offset..(data_offset + data_length)),
+                None => Err(corrupted()),
+            };
+            matched_ptr = Some(offset);
+            break;
+        }
+    }
+    span.exit(iterations, matched_ptr);
+    found
+}
+
+/// the `length` bytes at `offset` of `buffer`, `None` when they are out of it or the end
+/// overflows `usize`, the pointers read from the buffer reach `u32::MAX` which overflows on the
+/// 32 bits targets like wasm32
+#[inline]
+fn bytes_at(buffer: &[u8], offset: usize, length: usize) -> Option<&[u8]> {
+    buffer.get(offset..offset.checked_add(length)?)
+}
+
+/// read the `length` bytes at `offset` of `bytes` as a little-endian unsigned integer, the byte
+/// order of every number in the xdb header, vector index and ipv4 segment index
+///
+/// the xdb format only uses the lengths 1, 2 and 4, a length of 0 reads 0 and lengths over
+/// `size_of::<usize>()` overflow the shift
+///
+/// # Panics
+///
+/// if `offset + length` is out of `bytes`, prefer `read_u8`, `read_u16_le` and `read_u32_le`
+/// when the buffer is not checked yet
+#[inline(always)]
+pub fn get_block_by_size(bytes: &[u8], offset: usize, length: usize) -> usize {
+    let mut result: usize = 0;
+    for (index, value) in bytes[offset..offset + length].iter().enumerate() {
+        result += usize::from(*value) << (index << 3);
+    }
+    result
+}
+
+// the file pointers of the xdb format are u32, `ptr_at` widens them to `usize` losslessly
+const _: () = assert!(usize::BITS >= 32);
+
+/// the little-endian `u16` at `offset` of the checked `bytes`,
+/// the header fields and the region data lengths of the xdb format
+#[inline]
+pub(crate) fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+/// the little-endian `u32` at `offset` of the checked `bytes`
+#[inline]
+pub(crate) fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
+
+/// the little-endian `u32` file pointer at `offset` of the checked `bytes` as an index
+#[inline]
+pub(crate) fn ptr_at(bytes: &[u8], offset: usize) -> usize {
+    u32_at(bytes, offset) as usize
+}
+
+/// the byte at `offset` of `bytes`, `None` if it is out of `bytes`
+#[inline]
+pub fn read_u8(bytes: &[u8], offset: usize) -> Option<u8> {
+    bytes.get(offset).copied()
+}
+
+/// the little-endian `u16` at `offset` of `bytes`, `None` if it is out of `bytes`
+#[inline]
+pub fn read_u16_le(bytes: &[u8], offset: usize) -> Option<u16> {
+    let bytes = bytes.get(offset..offset.checked_add(2)?)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+/// the little-endian `u32` at `offset` of `bytes`, `None` if it is out of `bytes`
+#[inline]
+pub fn read_u32_le(bytes: &[u8], offset: usize) -> Option<u32> {
+    let bytes = bytes.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// search `ip` in `buf` holding the whole ipv4 xdb file and return the raw region bytes,
+/// it works without `std`, the header is checked on every call
+#[inline]
+pub fn core_search(buf: &[u8], ip: u32) -> Result<&[u8], Ip2RegionError> {
+    if check_header(buf, buf.len())? != <u32 as SegmentIp>::VERSION {
+        return Err(Ip2RegionError::IpVersionMismatch);
+    }
+    Ok(&buf[search_in_buffer(buf, vector_index_length(buf)?, ip)?])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_le() {
+        let bytes = [0x01, 0x02, 0x03, 0x04, 0x05];
+        assert_eq!(get_block_by_size(&bytes, 0, 0), 0);
+        assert_eq!(get_block_by_size(&bytes, 1, 1), 0x02);
+        assert_eq!(get_block_by_size(&bytes, 1, 2), 0x0302);
+        assert_eq!(get_block_by_size(&bytes, 1, 4), 0x0504_0302);
+        assert_eq!(u16_at(&bytes, 1), 0x0302);
+        assert_eq!(u32_at(&bytes, 1), 0x0504_0302);
+        assert_eq!(ptr_at(&[0xff; 4], 0), 0xffff_ffff);
+
+        assert_eq!(read_u8(&bytes, 4), Some(0x05));
+        assert_eq!(read_u8(&bytes, 5), None);
+        assert_eq!(read_u16_le(&bytes, 3), Some(0x0504));
+        assert_eq!(read_u16_le(&bytes, 4), None);
+        assert_eq!(read_u32_le(&bytes, 1), Some(0x0504_0302));
+        assert_eq!(read_u32_le(&bytes, 2), None);
+        assert_eq!(read_u32_le(&bytes, usize::MAX), None);
+    }
+
+    #[test]
+    fn test_bytes_at() {
+        let bytes = [1, 2, 3];
+        assert_eq!(bytes_at(&bytes, 1, 2), Some(&bytes[1..]));
+        assert_eq!(bytes_at(&bytes, 2, 2), None);
+        assert_eq!(bytes_at(&bytes, usize::MAX, 1), None);
+        assert_eq!(bytes_at(&bytes, 1, usize::MAX), None);
+    }
+
+    #[test]
+    fn test_vector_index_length() {
+        let mut header = [0; HEADER_INFO_LENGTH];
+        header[2] = 1;
+        assert_eq!(vector_index_length(&header).unwrap(), VECTOR_INDEX_LENGTH);
+        header[2] = 2;
+        assert!(matches!(
+            vector_index_length(&header),
+            Err(Ip2RegionError::InvalidDatabase { reason })
+                if reason == "unsupported index policy 2, expect 1"
+        ));
+    }
+}
@@ -0,0 +1,483 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::ops::Deref;
+use std::str::FromStr;
+
+use crate::Ip2RegionError;
+
+/// the region info of an ip, parsed from the `country|region|province|city|isp` format.
+///
+/// the `Default` fields are empty strings for building the partial records in the tests, it is
+/// not the `0|0|0|0|0` of the ips nothing is known about, see `is_fully_unknown` for that
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IpInfo {
+    pub country: String,
+    pub region: String,
+    pub province: String,
+    pub city: String,
+    pub isp: String,
+}
+
+impl IpInfo {
+    /// the raw pipe-delimited format stored in the xdb file, like `中国|0|广东省|深圳市|电信`
+    pub fn raw(&self) -> String {
+        self.to_string()
+    }
+
+    /// in mainland china, the country is `中国` and the province is none of hong kong, macao
+    /// and taiwan, which are written as `香港`, `澳门` and `台湾省` in the xdb file
+    pub fn is_chinese_mainland(&self) -> bool {
+        self.country == "中国"
+            && !matches!(self.province.as_str(), "香港" | "澳门" | "台湾" | "台湾省")
+    }
+
+    /// the autonomous system number in the isp field of the xdb distributions writing it like
+    /// `AS4134` or `电信 AS4134`, the first `AS` or `as` followed by the digits and not
+    /// preceded by a letter or a digit, the xdb file of this repository has none
+    pub fn asn(&self) -> Option<u32> {
+        let isp = self.isp.as_bytes();
+        (0..isp.len().saturating_sub(2)).find_map(|start| {
+            if !isp[start..start + 2].eq_ignore_ascii_case(b"AS")
+                || (start > 0 && isp[start - 1].is_ascii_alphanumeric())
+            {
+                return None;
+            }
+            let digits = &self.isp[start + 2..];
+            let length = digits
+                .bytes()
+                .position(|b| !b.is_ascii_digit())
+                .unwrap_or(digits.len());
+            digits[..length].parse().ok()
+        })
+    }
+
+    /// the unknown fields are written as `0` in the xdb file
+    const UNKNOWN: &'static str = "0";
+
+    fn fields(&self) -> [&str; 5] {
+        [
+            &self.country,
+            &self.region,
+            &self.province,
+            &self.city,
+            &self.isp,
+        ]
+    }
+
+    /// nothing is known about the ip, `0|0|0|0|0`
+    pub fn is_fully_unknown(&self) -> bool {
+        self.fields().iter().all(|&field| field == Self::UNKNOWN)
+    }
+
+    /// some of the fields are unknown like the city of `中国|0|广东省|0|电信`, the region field
+    /// is `0` in most of the regions of the xdb file
+    pub fn has_unknown_fields(&self) -> bool {
+        self.fields().contains(&Self::UNKNOWN)
+    }
+}
+
+/// compare to the raw pipe-delimited format field by field without formatting, for the
+/// assertions like `assert_eq!(info, "中国|0|广东省|深圳市|电信")`
+impl PartialEq<str> for IpInfo {
+    fn eq(&self, other: &str) -> bool {
+        self.fields().iter().copied().eq(other.split('|'))
+    }
+}
+
+impl PartialEq<&str> for IpInfo {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+impl PartialEq<String> for IpInfo {
+    fn eq(&self, other: &String) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl PartialEq<IpInfo> for &str {
+    fn eq(&self, other: &IpInfo) -> bool {
+        other == *self
+    }
+}
+
+impl PartialEq<IpInfo> for String {
+    fn eq(&self, other: &IpInfo) -> bool {
+        other == self.as_str()
+    }
+}
+
+/// the province or city name without its administrative suffix like `省` or `市`, the xdb file
+/// stores them both with and without the suffix
+#[cfg(any(feature = "locale", feature = "timezone"))]
+pub(crate) fn strip_division_suffix(name: &str) -> &str {
+    ["特别行政区", "自治区", "省", "市"]
+        .iter()
+        .find_map(|suffix| name.strip_suffix(suffix))
+        .unwrap_or(name)
+}
+
+impl Display for IpInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}|{}|{}|{}|{}",
+            self.country, self.region, self.province, self.city, self.isp
+        )
+    }
+}
+
+impl IpInfo {
+    /// parse the region data of the xdb file, a wrong format is an invalid database
+    pub(crate) fn from_region(region: &str) -> Result<Self, Ip2RegionError> {
+        IpInfo::split(region).ok_or_else(|| Ip2RegionError::InvalidDatabase {
+            reason: format!("region `{region}` should have 5 fields split by `|`"),
+        })
+    }
+
+    /// split exactly 5 fields, the unknown `0` fields are kept as they are
+    fn split(value: &str) -> Option<Self> {
+        let mut fields = value.split('|');
+        let mut next_field = || fields.next().map(str::to_owned);
+        match (
+            next_field(),
+            next_field(),
+            next_field(),
+            next_field(),
+            next_field(),
+            next_field(),
+        ) {
+            (Some(country), Some(region), Some(province), Some(city), Some(isp), None) => {
+                Some(IpInfo {
+                    country,
+                    region,
+                    province,
+                    city,
+                    isp,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// the region data of the xdb file, a wrong format is an invalid database
+impl TryFrom<String> for IpInfo {
+    type Error = Ip2RegionError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        IpInfo::from_region(&value)
+    }
+}
+
+/// parse the `raw` format back, like a region stored in a log or a database
+impl FromStr for IpInfo {
+    type Err = Ip2RegionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        IpInfo::split(s).ok_or_else(|| Ip2RegionError::ParseError(s.to_owned()))
+    }
+}
+
+/// the raw region string of a search, always 5 fields split by `|` like
+/// `中国|0|广东省|深圳市|电信`, it is used as a `&str` and converted into `IpInfo` when the
+/// fields are needed
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SearchResult(String);
+
+impl SearchResult {
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+/// the region data of the xdb file, a wrong format is an invalid database
+impl TryFrom<String> for SearchResult {
+    type Error = Ip2RegionError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if value.split('|').count() != 5 {
+            return Err(Ip2RegionError::InvalidDatabase {
+                reason: format!("region `{value}` should have 5 fields split by `|`"),
+            });
+        }
+        Ok(Self(value))
+    }
+}
+
+impl Deref for SearchResult {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for SearchResult {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for SearchResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<SearchResult> for String {
+    fn from(result: SearchResult) -> Self {
+        result.0
+    }
+}
+
+impl From<SearchResult> for IpInfo {
+    fn from(result: SearchResult) -> Self {
+        IpInfo::split(&result.0).expect("the fields are checked when the result is created")
+    }
+}
+
+/// ordered by the geography, country, province, city and then isp, the region field is
+/// only compared last to be consistent with `Eq`, the names are compared by their utf-8 bytes
+impl Ord for IpInfo {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (
+            &self.country,
+            &self.province,
+            &self.city,
+            &self.isp,
+            &self.region,
+        )
+            .cmp(&(
+                &other.country,
+                &other.province,
+                &other.city,
+                &other.isp,
+                &other.region,
+            ))
+    }
+}
+
+impl PartialOrd for IpInfo {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// the fields by their names, for the template engines taking a map as the context
+impl From<IpInfo> for HashMap<&'static str, String> {
+    fn from(info: IpInfo) -> Self {
+        HashMap::from([
+            ("country", info.country),
+            ("region", info.region),
+            ("province", info.province),
+            ("city", info.city),
+            ("isp", info.isp),
+        ])
+    }
+}
+
+/// the same object as the `Serialize` output
+#[cfg(feature = "serde")]
+impl From<IpInfo> for serde_json::Value {
+    fn from(info: IpInfo) -> Self {
+        serde_json::json!({
+            "country": info.country,
+            "region": info.region,
+            "province": info.province,
+            "city": info.city,
+            "isp": info.isp,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ip_info() {
+        let info = IpInfo::try_from("中国|0|广东省|深圳市|电信".to_owned()).unwrap();
+        assert_eq!(info.country, "中国");
+        assert_eq!(info.region, "0");
+        assert_eq!(info.province, "广东省");
+        assert_eq!(info.city, "深圳市");
+        assert_eq!(info.isp, "电信");
+        assert_eq!(info.raw(), "中国|0|广东省|深圳市|电信");
+    }
+
+    #[test]
+    fn test_from_str() {
+        let info: IpInfo = "中国|0|广东省|深圳市|电信".parse().unwrap();
+        assert_eq!(info, IpInfo::try_from(info.raw()).unwrap());
+        assert!(matches!(
+            "中国|0|广东省|深圳市".parse::<IpInfo>(),
+            Err(Ip2RegionError::ParseError(_))
+        ));
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_from_str_round_trip(fields in proptest::array::uniform5("[^|]*")) {
+            let [country, region, province, city, isp] = fields;
+            let info = IpInfo { country, region, province, city, isp };
+            proptest::prop_assert_eq!(info.to_string().parse::<IpInfo>().unwrap(), info);
+        }
+    }
+
+    #[test]
+    fn test_eq_raw() {
+        let raw = "中国|0|广东省|深圳市|电信";
+        let info: IpInfo = raw.parse().unwrap();
+        assert_eq!(info, raw);
+        assert_eq!(raw, info);
+        assert_eq!(info, raw.to_owned());
+        assert_eq!(raw.to_owned(), info);
+        for other in [
+            "中国|0|广东省|深圳市",
+            "中国|0|广东省|深圳市|电信|0",
+            "中国|0|广东省|广州市|电信",
+            "",
+        ] {
+            assert_ne!(info, other);
+        }
+    }
+
+    #[test]
+    fn test_asn() {
+        let asn = |isp: &str| {
+            IpInfo::from_str(&format!("中国|0|广东省|深圳市|{isp}"))
+                .unwrap()
+                .asn()
+        };
+        assert_eq!(asn("AS4134"), Some(4134));
+        assert_eq!(asn("电信 AS4134"), Some(4134));
+        assert_eq!(asn("电信AS4134"), Some(4134));
+        assert_eq!(asn("as13335 cloudflare"), Some(13335));
+        assert_eq!(asn("MASS AS7"), Some(7));
+        for isp in ["电信", "0", "AS", "ASN", "BAS4134", "AS99999999999", ""] {
+            assert_eq!(asn(isp), None, "{isp}");
+        }
+    }
+
+    #[test]
+    fn test_unknown_fields() {
+        let info: IpInfo = "0|0|0|0|0".parse().unwrap();
+        assert!(info.is_fully_unknown());
+        assert!(info.has_unknown_fields());
+        let info: IpInfo = "中国|0|广东省|0|电信".parse().unwrap();
+        assert!(!info.is_fully_unknown());
+        assert!(info.has_unknown_fields());
+        let info: IpInfo = "中国|华南|广东省|深圳市|电信".parse().unwrap();
+        assert!(!info.is_fully_unknown());
+        assert!(!info.has_unknown_fields());
+    }
+
+    #[test]
+    fn test_default() {
+        let info = IpInfo::default();
+        assert_eq!(info.to_string(), "||||");
+        assert!(!info.is_fully_unknown());
+        assert!(!info.has_unknown_fields());
+        let info = IpInfo {
+            country: "中国".to_owned(),
+            ..IpInfo::default()
+        };
+        assert_eq!(info, "中国||||");
+    }
+
+    #[test]
+    fn test_ord() {
+        let mut infos: Vec<IpInfo> = [
+            "美国|0|0|0|0",
+            "中国|0|广东省|深圳市|电信",
+            "日本|0|0|0|0",
+            "中国|0|北京|北京市|联通",
+            "中国|0|广东省|广州市|移动",
+        ]
+        .iter()
+        .map(|raw| raw.parse().unwrap())
+        .collect();
+        infos.sort();
+        let raws: Vec<String> = infos.iter().map(IpInfo::raw).collect();
+        assert_eq!(
+            raws,
+            [
+                "中国|0|北京|北京市|联通",
+                "中国|0|广东省|广州市|移动",
+                "中国|0|广东省|深圳市|电信",
+                "日本|0|0|0|0",
+                "美国|0|0|0|0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hash() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::collections::HashSet;
+        use std::hash::{Hash, Hasher};
+
+        let hash = |info: &IpInfo| {
+            let mut hasher = DefaultHasher::new();
+            info.hash(&mut hasher);
+            hasher.finish()
+        };
+        let info: IpInfo = "中国|0|广东省|深圳市|电信".parse().unwrap();
+        let same: IpInfo = "中国|0|广东省|深圳市|电信".parse().unwrap();
+        assert_eq!(hash(&info), hash(&same));
+
+        let infos: HashSet<IpInfo> = [info, same, "美国|0|0|0|0".parse().unwrap()]
+            .into_iter()
+            .collect();
+        assert_eq!(infos.len(), 2);
+    }
+
+    #[test]
+    fn test_search_result() {
+        let raw = "中国|0|广东省|深圳市|电信";
+        let result = SearchResult::try_from(raw.to_owned()).unwrap();
+        assert_eq!(&*result, raw);
+        assert_eq!(result.as_ref(), raw);
+        assert_eq!(result.to_string(), raw);
+        assert!(result.starts_with("中国"));
+        assert_eq!(IpInfo::from(result.clone()), raw);
+        assert_eq!(String::from(result), raw);
+        for raw in ["中国|0|广东省|深圳市", "中国|0|广东省|深圳市|电信|0"] {
+            assert!(matches!(
+                SearchResult::try_from(raw.to_owned()),
+                Err(Ip2RegionError::InvalidDatabase { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_into_map() {
+        let info: IpInfo = "中国|0|广东省|深圳市|电信".parse().unwrap();
+        let map: HashMap<_, _> = info.clone().into();
+        assert_eq!(map.len(), 5);
+        assert_eq!(map["province"], "广东省");
+        assert_eq!(map["isp"], "电信");
+
+        #[cfg(feature = "serde")]
+        {
+            let value = serde_json::Value::from(info.clone());
+            assert_eq!(value, serde_json::to_value(&info).unwrap());
+            assert_eq!(value["city"], "深圳市");
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid_ip_info() {
+        for region in ["", "中国|0|广东省|深圳市", "中国|0|广东省|深圳市|电信|0"]
+        {
+            assert!(matches!(
+                IpInfo::try_from(region.to_owned()),
+                Err(Ip2RegionError::InvalidDatabase { .. })
+            ));
+        }
+    }
+}
@@ -1,32 +1,191 @@
-use std::error::Error;
-use std::net::Ipv4Addr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::str::FromStr;
 
+use crate::Ip2RegionError;
+
 pub trait ToUIntIP {
-    fn to_u32_ip(&self) -> Result<u32, Box<dyn Error>>;
+    fn to_u32_ip(&self) -> Result<u32, Ip2RegionError>;
 }
 
 impl ToUIntIP for u32 {
-    fn to_u32_ip(&self) -> Result<u32, Box<dyn Error>> {
+    fn to_u32_ip(&self) -> Result<u32, Ip2RegionError> {
         Ok(self.to_owned())
     }
 }
 
+/// the dotted-decimal form, the decimal u32 form, or the cidr form `a.b.c.d/prefix` of the
+/// firewall logs which is searched as the address `a.b.c.d` as it is, without the mask applied
 impl ToUIntIP for &str {
-    fn to_u32_ip(&self) -> Result<u32, Box<dyn Error>> {
+    fn to_u32_ip(&self) -> Result<u32, Ip2RegionError> {
         if let Ok(ip_addr) = Ipv4Addr::from_str(self) {
             return Ok(u32::from(ip_addr));
         }
-        Ok(self.parse::<u32>()?)
+        if let Some((ip, prefix)) = self.split_once('/') {
+            return match (Ipv4Addr::from_str(ip), prefix.parse::<u8>()) {
+                (Ok(ip_addr), Ok(0..=32)) => Ok(u32::from(ip_addr)),
+                _ => Err(Ip2RegionError::InvalidIp(self.to_string())),
+            };
+        }
+        self.parse::<u32>()
+            .map_err(|_| Ip2RegionError::InvalidIp(self.to_string()))
     }
 }
 
 impl ToUIntIP for Ipv4Addr {
-    fn to_u32_ip(&self) -> Result<u32, Box<dyn Error>> {
+    fn to_u32_ip(&self) -> Result<u32, Ip2RegionError> {
         Ok(u32::from(*self))
     }
 }
 
+/// only the V4 variant can be converted, V6 goes to `ToUInt128IP`
+impl ToUIntIP for IpAddr {
+    fn to_u32_ip(&self) -> Result<u32, Ip2RegionError> {
+        match self {
+            IpAddr::V4(ip) => ip.to_u32_ip(),
+            IpAddr::V6(_) => Err(Ip2RegionError::IpVersionMismatch),
+        }
+    }
+}
+
+/// the client address of a connection, the port is dropped, only the V4 variant can be
+/// converted like `IpAddr`
+///
+/// ```ignore
+/// use std::net::SocketAddr;
+///
+/// use axum::{extract::ConnectInfo, extract::Request, middleware::Next, response::Response};
+///
+/// async fn region(
+///     ConnectInfo(addr): ConnectInfo<SocketAddr>,
+///     mut request: Request,
+///     next: Next,
+/// ) -> Response {
+///     if let Ok(info) = xdb::search_by_ip(addr) {
+///         request.extensions_mut().insert(info);
+///     }
+///     next.run(request).await
+/// }
+///
+/// let app = axum::Router::new().layer(axum::middleware::from_fn(region));
+/// axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
+/// ```
+impl ToUIntIP for SocketAddr {
+    fn to_u32_ip(&self) -> Result<u32, Ip2RegionError> {
+        self.ip().to_u32_ip()
+    }
+}
+
+/// the raw octets in network byte order, like the address of an ipv4 packet header
+impl ToUIntIP for [u8; 4] {
+    fn to_u32_ip(&self) -> Result<u32, Ip2RegionError> {
+        Ok(u32::from_be_bytes(*self))
+    }
+}
+
+/// the raw ipv6 octets go to `ToUInt128IP`
+impl ToUIntIP for [u8; 16] {
+    fn to_u32_ip(&self) -> Result<u32, Ip2RegionError> {
+        Err(Ip2RegionError::IpVersionMismatch)
+    }
+}
+
+/// the borrowed ips, so `search_by_ip(&addr)` works without a copy at the call site
+impl ToUIntIP for &u32 {
+    fn to_u32_ip(&self) -> Result<u32, Ip2RegionError> {
+        (**self).to_u32_ip()
+    }
+}
+
+impl ToUIntIP for &Ipv4Addr {
+    fn to_u32_ip(&self) -> Result<u32, Ip2RegionError> {
+        (**self).to_u32_ip()
+    }
+}
+
+impl ToUIntIP for &IpAddr {
+    fn to_u32_ip(&self) -> Result<u32, Ip2RegionError> {
+        (**self).to_u32_ip()
+    }
+}
+
+impl ToUIntIP for &SocketAddr {
+    fn to_u32_ip(&self) -> Result<u32, Ip2RegionError> {
+        (**self).to_u32_ip()
+    }
+}
+
+pub trait ToUInt128IP {
+    fn to_u128_ip(&self) -> Result<u128, Ip2RegionError>;
+}
+
+impl ToUInt128IP for u128 {
+    fn to_u128_ip(&self) -> Result<u128, Ip2RegionError> {
+        Ok(self.to_owned())
+    }
+}
+
+impl ToUInt128IP for &str {
+    fn to_u128_ip(&self) -> Result<u128, Ip2RegionError> {
+        Ipv6Addr::from_str(self)
+            .map(u128::from)
+            .map_err(|_| Ip2RegionError::InvalidIp(self.to_string()))
+    }
+}
+
+impl ToUInt128IP for Ipv6Addr {
+    fn to_u128_ip(&self) -> Result<u128, Ip2RegionError> {
+        Ok(u128::from(*self))
+    }
+}
+
+/// only the V6 variant can be converted, V4 goes to `ToUIntIP`
+impl ToUInt128IP for IpAddr {
+    fn to_u128_ip(&self) -> Result<u128, Ip2RegionError> {
+        match self {
+            IpAddr::V4(_) => Err(Ip2RegionError::IpVersionMismatch),
+            IpAddr::V6(ip) => ip.to_u128_ip(),
+        }
+    }
+}
+
+/// the port is dropped, only the V6 variant can be converted like `IpAddr`
+impl ToUInt128IP for SocketAddr {
+    fn to_u128_ip(&self) -> Result<u128, Ip2RegionError> {
+        self.ip().to_u128_ip()
+    }
+}
+
+/// the raw octets in network byte order
+impl ToUInt128IP for [u8; 16] {
+    fn to_u128_ip(&self) -> Result<u128, Ip2RegionError> {
+        Ok(u128::from_be_bytes(*self))
+    }
+}
+
+impl ToUInt128IP for &u128 {
+    fn to_u128_ip(&self) -> Result<u128, Ip2RegionError> {
+        (**self).to_u128_ip()
+    }
+}
+
+impl ToUInt128IP for &Ipv6Addr {
+    fn to_u128_ip(&self) -> Result<u128, Ip2RegionError> {
+        (**self).to_u128_ip()
+    }
+}
+
+impl ToUInt128IP for &IpAddr {
+    fn to_u128_ip(&self) -> Result<u128, Ip2RegionError> {
+        (**self).to_u128_ip()
+    }
+}
+
+impl ToUInt128IP for &SocketAddr {
+    fn to_u128_ip(&self) -> Result<u128, Ip2RegionError> {
+        (**self).to_u128_ip()
+    }
+}
+
 #[cfg(test)]
 mod test_ip {
     use super::*;
@@ -45,6 +204,28 @@ mod test_ip {
         assert_eq!(result, 12);
     }
 
+    #[test]
+    fn test_ip_cidr_str() {
+        assert_eq!(
+            "1.1.1.0/24".to_u32_ip().unwrap(),
+            1 << 24 | 1 << 16 | 1 << 8
+        );
+        assert_eq!(
+            "10.1.2.3/8".to_u32_ip().unwrap(),
+            10 << 24 | 1 << 16 | 2 << 8 | 3
+        );
+        assert_eq!("0.0.0.0/0".to_u32_ip().unwrap(), 0);
+        for ip in [
+            "1.1.1.0/33",
+            "1.1.1.0/",
+            "1.1.1.0/-1",
+            "1.1.1.0/24/8",
+            "12/8",
+        ] {
+            assert!(matches!(ip.to_u32_ip(), Err(Ip2RegionError::InvalidIp(_))));
+        }
+    }
+
     #[test]
     fn test_ip_u32() {
         let ip: u32 = 33;
@@ -58,4 +239,111 @@ mod test_ip {
         let result = ip.to_u32_ip().unwrap();
         assert_eq!(result, 3 << 8 | 12)
     }
+
+    #[test]
+    fn test_ip_addr_big_endian() {
+        for ip in ["0.0.0.0", "1.2.3.4", "128.0.0.1", "255.255.255.255"] {
+            let ip = Ipv4Addr::from_str(ip).unwrap();
+            assert_eq!(ip.to_u32_ip().unwrap(), u32::from(ip));
+            assert_eq!(ip.to_u32_ip().unwrap(), u32::from_be_bytes(ip.octets()));
+        }
+    }
+
+    #[test]
+    fn test_octets() {
+        assert_eq!(
+            [1u8, 1, 1, 1].to_u32_ip().unwrap(),
+            Ipv4Addr::new(1, 1, 1, 1).to_u32_ip().unwrap()
+        );
+        let ip = Ipv6Addr::from_str("2001:db8::1").unwrap();
+        assert_eq!(ip.octets().to_u128_ip().unwrap(), ip.to_u128_ip().unwrap());
+        assert!(matches!(
+            ip.octets().to_u32_ip(),
+            Err(Ip2RegionError::IpVersionMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_ipv6_str_2_u128() {
+        let result = "2001:db8::1".to_u128_ip().unwrap();
+        assert_eq!(result, 0x2001_0db8 << 96 | 1);
+        assert_eq!(
+            Ipv6Addr::from_str("2001:db8::1")
+                .unwrap()
+                .to_u128_ip()
+                .unwrap(),
+            result
+        );
+        assert!("1.1.1.1".to_u128_ip().is_err());
+    }
+
+    #[test]
+    fn test_ip_addr_enum() {
+        let ip = IpAddr::from_str("0.0.3.12").unwrap();
+        assert_eq!(ip.to_u32_ip().unwrap(), 3 << 8 | 12);
+        assert!(matches!(
+            ip.to_u128_ip(),
+            Err(Ip2RegionError::IpVersionMismatch)
+        ));
+
+        let ip = IpAddr::from_str("2001:db8::1").unwrap();
+        assert_eq!(ip.to_u128_ip().unwrap(), 0x2001_0db8 << 96 | 1);
+        assert!(matches!(
+            ip.to_u32_ip(),
+            Err(Ip2RegionError::IpVersionMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_socket_addr() {
+        let addr = SocketAddr::from_str("1.0.1.0:8080").unwrap();
+        assert_eq!(addr.to_u32_ip().unwrap(), 1 << 24 | 1 << 8);
+        assert!(matches!(
+            addr.to_u128_ip(),
+            Err(Ip2RegionError::IpVersionMismatch)
+        ));
+        assert_eq!(
+            crate::search_by_ip(addr).unwrap().raw(),
+            "中国|0|福建省|福州市|电信"
+        );
+
+        let addr = SocketAddr::from_str("[2001:db8::1]:443").unwrap();
+        assert_eq!(addr.to_u128_ip().unwrap(), 0x2001_0db8 << 96 | 1);
+        assert!(matches!(
+            addr.to_u32_ip(),
+            Err(Ip2RegionError::IpVersionMismatch)
+        ));
+    }
+
+    // the borrows are what is tested
+    #[allow(clippy::needless_borrows_for_generic_args)]
+    #[test]
+    fn test_ref_variants() {
+        fn to_u32_ip<T: ToUIntIP>(ip: T) -> u32 {
+            ip.to_u32_ip().unwrap()
+        }
+        fn to_u128_ip<T: ToUInt128IP>(ip: T) -> u128 {
+            ip.to_u128_ip().unwrap()
+        }
+
+        let ip = Ipv4Addr::new(0, 0, 3, 12);
+        assert_eq!(to_u32_ip(&ip), 3 << 8 | 12);
+        assert_eq!(to_u32_ip(&IpAddr::V4(ip)), 3 << 8 | 12);
+        assert_eq!(to_u32_ip(&780u32), 780);
+        assert_eq!(to_u32_ip(&SocketAddr::new(IpAddr::V4(ip), 80)), 3 << 8 | 12);
+        let ip = Ipv6Addr::from_str("2001:db8::1").unwrap();
+        assert_eq!(to_u128_ip(&ip), 0x2001_0db8 << 96 | 1);
+        assert_eq!(to_u128_ip(&IpAddr::V6(ip)), 0x2001_0db8 << 96 | 1);
+        assert_eq!(to_u128_ip(&1u128), 1);
+        assert_eq!(
+            to_u128_ip(&SocketAddr::new(IpAddr::V6(ip), 443)),
+            0x2001_0db8 << 96 | 1
+        );
+
+        let ip = Ipv4Addr::new(1, 0, 1, 0);
+        assert_eq!(
+            crate::search_by_ip(&ip).unwrap(),
+            crate::search_by_ip(ip).unwrap()
+        );
+    }
 }
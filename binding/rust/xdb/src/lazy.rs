@@ -0,0 +1,121 @@
+//! a searcher built on the first use, for the `static` searchers of the web services
+use std::fmt;
+use std::ops::Deref;
+use std::sync::{Mutex, OnceLock, PoisonError};
+
+use crate::{Ip2RegionError, Searcher};
+
+/// a searcher built by `init` on the first use and shared since then, like
+/// `once_cell::sync::Lazy` but the build may fail, so it can be a `static` without
+/// calling `searcher_init` in `main`
+///
+/// ```no_run
+/// use xdb::{LazySearcher, Searcher};
+///
+/// static SEARCHER: LazySearcher = LazySearcher::new(|| Searcher::new("data/ip2region.xdb"));
+///
+/// let info = SEARCHER.search("1.0.1.0").unwrap();
+/// ```
+pub struct LazySearcher<F = fn() -> Result<Searcher, Ip2RegionError>> {
+    searcher: OnceLock<Searcher>,
+    /// held while `init` runs, so the xdb file is loaded once by the racing first uses
+    init_lock: Mutex<()>,
+    init: F,
+}
+
+impl<F> LazySearcher<F>
+where
+    F: Fn() -> Result<Searcher, Ip2RegionError>,
+{
+    /// `init` is not called until the first use
+    pub const fn new(init: F) -> Self {
+        Self {
+            searcher: OnceLock::new(),
+            init_lock: Mutex::new(()),
+            init,
+        }
+    }
+
+    /// the searcher, built by `init` if it is not yet, a failed build is returned and `init`
+    /// runs again on the next use
+    pub fn try_get(&self) -> Result<&Searcher, Ip2RegionError> {
+        if let Some(searcher) = self.searcher.get() {
+            return Ok(searcher);
+        }
+        let _init = self
+            .init_lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(searcher) = self.searcher.get() {
+            return Ok(searcher);
+        }
+        let searcher = (self.init)()?;
+        // only set under the lock, so it is always empty here
+        Ok(self.searcher.get_or_init(|| searcher))
+    }
+
+    /// the searcher if it is already built
+    pub fn get(&self) -> Option<&Searcher> {
+        self.searcher.get()
+    }
+}
+
+/// # Panics
+///
+/// if the searcher is not built yet and `init` fails, use `try_get` to handle the error
+impl<F> Deref for LazySearcher<F>
+where
+    F: Fn() -> Result<Searcher, Ip2RegionError>,
+{
+    type Target = Searcher;
+
+    fn deref(&self) -> &Searcher {
+        match self.try_get() {
+            Ok(searcher) => searcher,
+            Err(e) => panic!("the lazy searcher can not be built: {e}"),
+        }
+    }
+}
+
+impl<F> fmt::Debug for LazySearcher<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LazySearcher")
+            .field("initialized", &self.searcher.get().is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+
+    use super::*;
+    use crate::searcher::default_detect_xdb_file;
+
+    static INITS: AtomicUsize = AtomicUsize::new(0);
+    static SEARCHER: LazySearcher = LazySearcher::new(|| {
+        INITS.fetch_add(1, Ordering::SeqCst);
+        Searcher::new(default_detect_xdb_file()?)
+    });
+
+    #[test]
+    fn test_lazy_searcher() {
+        assert!(SEARCHER.get().is_none());
+        let handles: Vec<_> = (0..8)
+            .map(|_| thread::spawn(|| SEARCHER.search("1.0.1.0").unwrap()))
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), "中国|0|福建省|福州市|电信");
+        }
+        assert_eq!(INITS.load(Ordering::SeqCst), 1);
+        assert!(SEARCHER.get().is_some());
+
+        let missing = LazySearcher::new(|| Searcher::new("not_exists.xdb"));
+        assert!(matches!(
+            missing.try_get(),
+            Err(Ip2RegionError::FileNotFound(_))
+        ));
+        assert!(missing.get().is_none());
+    }
+}
@@ -1,4 +1,124 @@
+//! the rust binding for ip2region, the `std` feature (on by default) provides the `Searcher`,
+//! without it only `core_search` over a buffer of the whole xdb file is available on
+//! `no_std + alloc` targets
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "async")]
+mod async_search;
+#[cfg(feature = "async")]
+pub use self::async_search::search_by_ip_async;
+#[cfg(feature = "std")]
+mod borrowed;
+#[cfg(feature = "std")]
+pub use self::borrowed::BorrowedSearcher;
+#[cfg(feature = "std")]
+mod builder;
+#[cfg(feature = "std")]
+pub use self::builder::SearcherBuilder;
+#[cfg(feature = "std")]
+mod cache_policy;
+#[cfg(feature = "lru")]
+mod cached_searcher;
+#[cfg(feature = "std")]
+pub use self::cache_policy::CachePolicy;
+#[cfg(feature = "lru")]
+pub use self::cached_searcher::CachedSearcher;
+mod checksum;
+pub use self::checksum::{build_verified_xdb, verify_xdb_checksum};
+#[cfg(feature = "embed")]
+mod embed;
+#[cfg(feature = "embed")]
+pub use self::embed::EMBEDDED_XDB;
+mod error;
+#[cfg(feature = "std")]
+mod export;
+pub use self::error::Ip2RegionError;
+#[cfg(feature = "std")]
+mod fallback;
+#[cfg(feature = "std")]
+pub use self::fallback::{FallbackSearcher, Search};
+mod format;
+pub use self::format::{
+    core_search, get_block_by_size, read_u16_le, read_u32_le, read_u8, validate_header, IpVersion,
+    HEADER_INFO_LENGTH, IPV6_SEGMENT_INDEX_SIZE, SEGMENT_INDEX_SIZE, VECTOR_INDEX_COLS,
+    VECTOR_INDEX_LENGTH, VECTOR_INDEX_SIZE,
+};
+#[cfg(feature = "std")]
+mod ip_info;
+#[cfg(feature = "std")]
+pub use self::ip_info::{IpInfo, SearchResult};
+#[cfg(feature = "std")]
 mod ip_value;
-pub use self::ip_value::ToUIntIP;
+#[cfg(feature = "std")]
+pub use self::ip_value::{ToUInt128IP, ToUIntIP};
+#[cfg(feature = "std")]
+mod lazy;
+#[cfg(feature = "std")]
+pub use self::lazy::LazySearcher;
+#[cfg(feature = "locale")]
+mod locale;
+#[cfg(feature = "locale")]
+pub use self::locale::Locale;
+#[cfg(feature = "std")]
+mod maker;
+#[cfg(feature = "maxminddb")]
+mod maxmind;
+#[cfg(feature = "std")]
+mod merge;
+#[cfg(feature = "std")]
+mod mini;
+#[cfg(feature = "std")]
+pub use self::mini::MiniSearcher;
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "rayon")]
+pub use self::parallel::parallel_search;
+#[cfg(feature = "std")]
+mod pool;
+#[cfg(feature = "std")]
+pub use self::pool::{PooledSearcher, SearcherPool};
+#[cfg(feature = "remote")]
+mod remote;
+#[cfg(feature = "std")]
 pub mod searcher;
-pub use searcher::{search_by_ip, searcher_init};
+#[cfg(feature = "std")]
+pub use searcher::{
+    search_by_ip, search_by_ipv6, search_many, searcher_init, CachedBytes, CachedStr, DatabaseInfo,
+    ReadSeek, Searcher,
+};
+#[cfg(feature = "std")]
+mod segment;
+#[cfg(feature = "tower")]
+mod service;
+#[cfg(feature = "stats")]
+mod stats;
+#[cfg(feature = "timezone")]
+mod timezone;
+#[cfg(feature = "std")]
+pub use self::segment::{Segment, SegmentIter};
+#[cfg(feature = "stats")]
+pub use self::stats::SearchStats;
+/// the network type of `Searcher::search_range`
+#[cfg(feature = "std")]
+pub use ipnetwork::Ipv4Network;
+#[cfg(feature = "std")]
+mod validate;
+#[cfg(feature = "std")]
+pub use self::validate::{validate_against_testfile, ValidationReport};
+#[cfg(feature = "watch")]
+mod watch;
+#[cfg(feature = "watch")]
+pub use self::watch::WatchHandle;
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(feature = "wasm")]
+pub use self::wasm::{init, search_by_ip as search_by_ip_js, WasmSearcher};
+
+// a searcher is shared between threads, keep it `Send + Sync` when its fields change
+#[cfg(feature = "std")]
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Searcher>();
+};
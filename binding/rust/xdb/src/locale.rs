@@ -0,0 +1,209 @@
+//! a small bundled table of english and pinyin names for the most common regions of the xdb
+//! file, the names out of the table are returned as they are stored
+use crate::ip_info::strip_division_suffix;
+use crate::{Ip2RegionError, IpInfo, Searcher, ToUIntIP};
+
+/// the script of the region names returned by `Searcher::search_with_locale`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locale {
+    /// the names as they are stored in the xdb file
+    #[default]
+    Chinese,
+    /// english names for countries and isps, pinyin for chinese provinces and cities
+    EnglishPinyin,
+}
+
+impl Searcher {
+    /// same as `search`, but the region names are translated to `locale`
+    pub fn search_with_locale<T>(&self, ip: T, locale: Locale) -> Result<IpInfo, Ip2RegionError>
+    where
+        T: ToUIntIP,
+    {
+        Ok(locale.translate(self.search(ip)?))
+    }
+}
+
+impl Locale {
+    /// translate every field of `info`, the unknown `0` is kept
+    pub fn translate(self, info: IpInfo) -> IpInfo {
+        match self {
+            Locale::Chinese => info,
+            Locale::EnglishPinyin => IpInfo {
+                country: translate(&COUNTRIES, info.country),
+                region: info.region,
+                province: translate(&PROVINCES, info.province),
+                city: translate(&CITIES, info.city),
+                isp: translate(&ISPS, info.isp),
+            },
+        }
+    }
+}
+
+fn translate(table: &[(&str, &str)], name: String) -> String {
+    let key = strip_division_suffix(&name);
+    table
+        .iter()
+        .find(|(chinese, _)| *chinese == key)
+        .map_or(name, |(_, english)| (*english).to_owned())
+}
+
+const COUNTRIES: [(&str, &str); 40] = [
+    ("中国", "China"),
+    ("美国", "United States"),
+    ("日本", "Japan"),
+    ("英国", "United Kingdom"),
+    ("加拿大", "Canada"),
+    ("法国", "France"),
+    ("西班牙", "Spain"),
+    ("印度", "India"),
+    ("俄罗斯", "Russia"),
+    ("德国", "Germany"),
+    ("澳大利亚", "Australia"),
+    ("意大利", "Italy"),
+    ("荷兰", "Netherlands"),
+    ("越南", "Vietnam"),
+    ("印度尼西亚", "Indonesia"),
+    ("泰国", "Thailand"),
+    ("巴西", "Brazil"),
+    ("马来西亚", "Malaysia"),
+    ("菲律宾", "Philippines"),
+    ("波兰", "Poland"),
+    ("挪威", "Norway"),
+    ("瑞士", "Switzerland"),
+    ("新加坡", "Singapore"),
+    ("瑞典", "Sweden"),
+    ("罗马尼亚", "Romania"),
+    ("匈牙利", "Hungary"),
+    ("乌克兰", "Ukraine"),
+    ("爱尔兰", "Ireland"),
+    ("捷克", "Czechia"),
+    ("土耳其", "Turkey"),
+    ("奥地利", "Austria"),
+    ("保加利亚", "Bulgaria"),
+    ("韩国", "South Korea"),
+    ("芬兰", "Finland"),
+    ("新西兰", "New Zealand"),
+    ("阿根廷", "Argentina"),
+    ("欧洲", "Europe"),
+    ("丹麦", "Denmark"),
+    ("比利时", "Belgium"),
+    ("南非", "South Africa"),
+];
+
+const PROVINCES: [(&str, &str); 34] = [
+    ("北京", "Beijing"),
+    ("天津", "Tianjin"),
+    ("上海", "Shanghai"),
+    ("重庆", "Chongqing"),
+    ("河北", "Hebei"),
+    ("山西", "Shanxi"),
+    ("辽宁", "Liaoning"),
+    ("吉林", "Jilin"),
+    ("黑龙江", "Heilongjiang"),
+    ("江苏", "Jiangsu"),
+    ("浙江", "Zhejiang"),
+    ("安徽", "Anhui"),
+    ("福建", "Fujian"),
+    ("江西", "Jiangxi"),
+    ("山东", "Shandong"),
+    ("河南", "Henan"),
+    ("湖北", "Hubei"),
+    ("湖南", "Hunan"),
+    ("广东", "Guangdong"),
+    ("海南", "Hainan"),
+    ("四川", "Sichuan"),
+    ("贵州", "Guizhou"),
+    ("云南", "Yunnan"),
+    ("陕西", "Shaanxi"),
+    ("甘肃", "Gansu"),
+    ("青海", "Qinghai"),
+    ("台湾", "Taiwan"),
+    ("内蒙古", "Inner Mongolia"),
+    ("广西", "Guangxi"),
+    ("西藏", "Tibet"),
+    ("宁夏", "Ningxia"),
+    ("新疆", "Xinjiang"),
+    ("香港", "Hong Kong"),
+    ("澳门", "Macau"),
+];
+
+const CITIES: [(&str, &str); 36] = [
+    ("北京", "Beijing"),
+    ("天津", "Tianjin"),
+    ("上海", "Shanghai"),
+    ("重庆", "Chongqing"),
+    ("广州", "Guangzhou"),
+    ("深圳", "Shenzhen"),
+    ("福州", "Fuzhou"),
+    ("厦门", "Xiamen"),
+    ("杭州", "Hangzhou"),
+    ("宁波", "Ningbo"),
+    ("成都", "Chengdu"),
+    ("台北", "Taipei"),
+    ("南京", "Nanjing"),
+    ("苏州", "Suzhou"),
+    ("无锡", "Wuxi"),
+    ("武汉", "Wuhan"),
+    ("郑州", "Zhengzhou"),
+    ("西安", "Xi'an"),
+    ("南宁", "Nanning"),
+    ("长沙", "Changsha"),
+    ("济南", "Jinan"),
+    ("青岛", "Qingdao"),
+    ("呼和浩特", "Hohhot"),
+    ("南昌", "Nanchang"),
+    ("沈阳", "Shenyang"),
+    ("大连", "Dalian"),
+    ("合肥", "Hefei"),
+    ("哈尔滨", "Harbin"),
+    ("乌鲁木齐", "Urumqi"),
+    ("太原", "Taiyuan"),
+    ("石家庄", "Shijiazhuang"),
+    ("长春", "Changchun"),
+    ("昆明", "Kunming"),
+    ("贵阳", "Guiyang"),
+    ("兰州", "Lanzhou"),
+    ("海口", "Haikou"),
+];
+
+const ISPS: [(&str, &str); 12] = [
+    ("电信", "China Telecom"),
+    ("联通", "China Unicom"),
+    ("移动", "China Mobile"),
+    ("铁通", "China Tietong"),
+    ("教育网", "CERNET"),
+    ("科技网", "CSTNET"),
+    ("鹏博士", "Dr. Peng"),
+    ("阿里巴巴", "Alibaba"),
+    ("阿里云", "Alibaba Cloud"),
+    ("谷歌", "Google"),
+    ("微软", "Microsoft"),
+    ("中华电信", "Chunghwa Telecom"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::searcher::default_detect_xdb_file;
+
+    #[test]
+    fn test_search_with_locale() {
+        let searcher = Searcher::new(default_detect_xdb_file().unwrap()).unwrap();
+        let info = searcher
+            .search_with_locale("1.0.1.0", Locale::EnglishPinyin)
+            .unwrap();
+        assert_eq!(info.to_string(), "China|0|Fujian|Fuzhou|China Telecom");
+        assert_eq!(
+            searcher
+                .search_with_locale("1.0.1.0", Locale::Chinese)
+                .unwrap(),
+            searcher.search("1.0.1.0").unwrap()
+        );
+
+        let info = IpInfo::try_from("中国|0|内蒙古|伊犁|0".to_owned()).unwrap();
+        assert_eq!(
+            Locale::EnglishPinyin.translate(info).to_string(),
+            "China|0|Inner Mongolia|伊犁|0"
+        );
+    }
+}
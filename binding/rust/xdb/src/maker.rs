@@ -0,0 +1,118 @@
+//! build xdb buffers from the segments, for `Searcher::merge` and the tests, see `maker/golang`
+//! of ip2region for the full maker
+use std::collections::HashMap;
+
+use crate::format::{
+    SegmentIp, HEADER_INFO_LENGTH, VECTOR_INDEX_LENGTH, VECTOR_INDEX_POLICY, VECTOR_INDEX_SIZE,
+    XDB_STRUCTURE_20, XDB_STRUCTURE_30,
+};
+
+pub(crate) trait MakerIp: SegmentIp + Ord {
+    /// the last ip sharing the same vector index entry with this ip
+    fn vector_index_end(self) -> Self;
+
+    fn next(self) -> Self;
+
+    fn segment_bytes(self) -> Vec<u8>;
+}
+
+impl MakerIp for u32 {
+    fn vector_index_end(self) -> Self {
+        self | 0xFFFF
+    }
+
+    fn next(self) -> Self {
+        self + 1
+    }
+
+    fn segment_bytes(self) -> Vec<u8> {
+        self.to_le_bytes().to_vec()
+    }
+}
+
+impl MakerIp for u128 {
+    fn vector_index_end(self) -> Self {
+        self | ((1 << 112) - 1)
+    }
+
+    fn next(self) -> Self {
+        self + 1
+    }
+
+    fn segment_bytes(self) -> Vec<u8> {
+        self.to_be_bytes().to_vec()
+    }
+}
+
+/// build a xdb buffer from the sorted and non overlapping `(start_ip, end_ip, region)` segments,
+/// there may be gaps between them which are not matched.
+///
+/// the end pointer of a vector index entry is one past its last segment index entry like the
+/// xdb files of the maker, and an entry with no segment points both pointers at the first
+/// segment index entry, an empty block never matches
+pub(crate) fn make_xdb<I: MakerIp>(segments: &[(I, I, &str)]) -> Vec<u8> {
+    let mut header = vec![0; HEADER_INFO_LENGTH];
+    let mut vector_index = vec![0; VECTOR_INDEX_LENGTH];
+    let mut region_data = Vec::new();
+    let mut region_offsets = HashMap::new();
+    let data_start = HEADER_INFO_LENGTH + VECTOR_INDEX_LENGTH;
+    for (_, _, region) in segments {
+        region_offsets.entry(*region).or_insert_with(|| {
+            let offset = data_start + region_data.len();
+            region_data.extend_from_slice(region.as_bytes());
+            offset
+        });
+    }
+
+    // a segment index entry never crosses a vector index entry
+    let mut pieces = Vec::new();
+    for &(start_ip, end_ip, region) in segments {
+        let mut start_ip = start_ip;
+        loop {
+            let piece_end = start_ip.vector_index_end().min(end_ip);
+            pieces.push((start_ip, piece_end, region));
+            if piece_end == end_ip {
+                break;
+            }
+            start_ip = piece_end.next();
+        }
+    }
+
+    let index_start = data_start + region_data.len();
+    let mut segment_index = Vec::new();
+    for (i, (start_ip, end_ip, region)) in pieces.iter().enumerate() {
+        let ptr = (index_start + i * I::SEGMENT_INDEX_SIZE) as u32;
+        let idx = start_ip.vector_index_offset();
+        if vector_index[idx..idx + 4] == [0; 4] {
+            vector_index[idx..idx + 4].copy_from_slice(&ptr.to_le_bytes());
+        }
+        let end_ptr = ptr + I::SEGMENT_INDEX_SIZE as u32;
+        vector_index[idx + 4..idx + 8].copy_from_slice(&end_ptr.to_le_bytes());
+
+        segment_index.extend(start_ip.segment_bytes());
+        segment_index.extend(end_ip.segment_bytes());
+        segment_index.extend((region.len() as u16).to_le_bytes());
+        segment_index.extend((region_offsets[region] as u32).to_le_bytes());
+    }
+
+    for entry in vector_index.chunks_exact_mut(VECTOR_INDEX_SIZE) {
+        if entry == [0; VECTOR_INDEX_SIZE] {
+            entry[0..4].copy_from_slice(&(index_start as u32).to_le_bytes());
+            entry[4..8].copy_from_slice(&(index_start as u32).to_le_bytes());
+        }
+    }
+
+    let end_index_ptr = index_start + segment_index.len() - I::SEGMENT_INDEX_SIZE;
+    if I::BYTES == 4 {
+        header[0..2].copy_from_slice(&XDB_STRUCTURE_20.to_le_bytes());
+    } else {
+        header[0..2].copy_from_slice(&XDB_STRUCTURE_30.to_le_bytes());
+        header[16..18].copy_from_slice(&6u16.to_le_bytes());
+        header[18..20].copy_from_slice(&4u16.to_le_bytes());
+    }
+    header[2..4].copy_from_slice(&VECTOR_INDEX_POLICY.to_le_bytes());
+    header[8..12].copy_from_slice(&(index_start as u32).to_le_bytes());
+    header[12..16].copy_from_slice(&(end_index_ptr as u32).to_le_bytes());
+
+    [header, vector_index, region_data, segment_index].concat()
+}
@@ -0,0 +1,87 @@
+//! convert the maxmind geoip2 city records to `IpInfo`, for measuring how far the two databases
+//! agree before migrating from maxmind
+use std::collections::BTreeMap;
+
+use maxminddb::geoip2::City;
+
+use crate::IpInfo;
+
+/// the field of the unknown names, like the xdb file
+const UNKNOWN: &str = "0";
+
+/// the fields in the xdb file style, the `zh-CN` names or else the `en` ones and `0` for the
+/// unknown, maxmind has no isp in the city database and the region of the xdb file is always
+/// `0`. hong kong, macao and taiwan are countries in maxmind but provinces of `中国` in the
+/// xdb file, they are moved to the province.
+///
+/// the names are kept as maxmind writes them, a province may differ from the xdb file by its
+/// suffix like `省`
+impl From<City<'_>> for IpInfo {
+    fn from(city: City<'_>) -> Self {
+        let country = city.country.as_ref();
+        let province = match country.and_then(|country| country.iso_code) {
+            Some("HK") => Some("香港"),
+            Some("MO") => Some("澳门"),
+            Some("TW") => Some("台湾省"),
+            _ => None,
+        };
+        let (country, province) = match province {
+            Some(province) => ("中国".to_owned(), province.to_owned()),
+            None => (
+                name(country.and_then(|country| country.names.as_ref())),
+                name(
+                    city.subdivisions
+                        .as_ref()
+                        .and_then(|subdivisions| subdivisions.first())
+                        .and_then(|subdivision| subdivision.names.as_ref()),
+                ),
+            ),
+        };
+        IpInfo {
+            country,
+            region: UNKNOWN.to_owned(),
+            province,
+            city: name(city.city.as_ref().and_then(|city| city.names.as_ref())),
+            isp: UNKNOWN.to_owned(),
+        }
+    }
+}
+
+fn name(names: Option<&BTreeMap<&str, &str>>) -> String {
+    names
+        .and_then(|names| names.get("zh-CN").or_else(|| names.get("en")))
+        .copied()
+        .unwrap_or(UNKNOWN)
+        .to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_city() {
+        let city: City = serde_json::from_str(
+            r#"{
+                "city": {"names": {"en": "Fuzhou", "zh-CN": "福州"}},
+                "country": {"iso_code": "CN", "names": {"en": "China", "zh-CN": "中国"}},
+                "subdivisions": [{"iso_code": "FJ", "names": {"en": "Fujian", "zh-CN": "福建省"}}]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(IpInfo::from(city), "中国|0|福建省|福州|0");
+
+        let city: City = serde_json::from_str(
+            r#"{"country": {"iso_code": "HK", "names": {"en": "Hong Kong", "zh-CN": "香港"}}}"#,
+        )
+        .unwrap();
+        assert_eq!(IpInfo::from(city), "中国|0|香港|0|0");
+
+        let city: City =
+            serde_json::from_str(r#"{"country": {"iso_code": "IS", "names": {"en": "Iceland"}}}"#)
+                .unwrap();
+        assert_eq!(IpInfo::from(city), "Iceland|0|0|0|0");
+        let city: City = serde_json::from_str("{}").unwrap();
+        assert!(IpInfo::from(city).is_fully_unknown());
+    }
+}
@@ -0,0 +1,137 @@
+//! combine the ipv4 xdb shards into one searcher, the write path complementing `export_csv`
+use std::net::Ipv4Addr;
+
+use crate::maker::make_xdb;
+use crate::{Ip2RegionError, Searcher};
+
+impl Searcher {
+    /// build a searcher of `CachePolicy::Full` from the segments of all the `shards`, for
+    /// the databases split into regional shards, the ips covered by none of them are not
+    /// matched.
+    ///
+    /// the same segment with the same region in several shards is kept once, any other
+    /// overlapping segments are an `InvalidDatabase` error naming both of them. only ipv4
+    /// shards are supported like `iter_segments`
+    pub fn merge(shards: Vec<Searcher>) -> Result<Searcher, Ip2RegionError> {
+        let mut segments = Vec::new();
+        for shard in &shards {
+            for segment in shard.iter_segments() {
+                let segment = segment?;
+                segments.push((segment.start_ip, segment.end_ip, segment.info.to_string()));
+            }
+        }
+        drop(shards);
+        segments.sort_unstable();
+        segments.dedup();
+        if segments.is_empty() {
+            return Err(Ip2RegionError::InvalidDatabase {
+                reason: "no segment to merge".to_owned(),
+            });
+        }
+        if let Some(pair) = segments.windows(2).find(|pair| pair[1].0 <= pair[0].1) {
+            return Err(Ip2RegionError::InvalidDatabase {
+                reason: format!(
+                    "the shards overlap, {}-{} of {} and {}-{} of {}",
+                    Ipv4Addr::from(pair[0].0),
+                    Ipv4Addr::from(pair[0].1),
+                    pair[0].2,
+                    Ipv4Addr::from(pair[1].0),
+                    Ipv4Addr::from(pair[1].1),
+                    pair[1].2
+                ),
+            });
+        }
+
+        let segments: Vec<_> = segments
+            .iter()
+            .map(|(start_ip, end_ip, region)| (*start_ip, *end_ip, region.as_str()))
+            .collect();
+        Searcher::from_bytes(make_xdb(&segments))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::format::u32_at;
+
+    use super::*;
+
+    fn shard(segments: &[(u32, u32, &str)]) -> Searcher {
+        Searcher::from_bytes(make_xdb(segments)).unwrap()
+    }
+
+    #[test]
+    fn test_merge() {
+        let asia = shard(&[
+            (0x0100_0000, 0x0100_00FF, "澳大利亚|0|0|0|0"),
+            (0x0100_0100, 0x0100_03FF, "中国|0|福建省|福州市|电信"),
+        ]);
+        let america = shard(&[(0x0300_0000, 0x0301_00FF, "美国|0|0|0|0")]);
+        let reserved = shard(&[(0, 0x00FF_FFFF, "0|0|0|内网IP|内网IP")]);
+        let merged = Searcher::merge(vec![america, asia, reserved]).unwrap();
+
+        assert_eq!(merged.search("0.0.0.0").unwrap().city, "内网IP");
+        assert_eq!(merged.search("1.0.0.255").unwrap().country, "澳大利亚");
+        assert_eq!(merged.search("1.0.1.0").unwrap().province, "福建省");
+        assert_eq!(merged.search("3.1.0.255").unwrap().country, "美国");
+        // the gaps between the shards, inside a vector index entry, after the last segment of
+        // one and in an entry with no segment
+        for ip in ["1.0.4.0", "3.1.1.0", "2.0.0.0", "255.255.255.255"] {
+            assert!(matches!(merged.search(ip), Err(Ip2RegionError::NotMatched)));
+        }
+        let segments: Vec<_> = merged.iter_segments().map(Result::unwrap).collect();
+        assert_eq!(segments.len(), 256 + 2 + 2);
+        assert!(segments
+            .windows(2)
+            .all(|pair| pair[0].end_ip < pair[1].start_ip));
+
+        // the end pointer of every vector index entry is one past its last segment index
+        // entry like the real xdb files, the start of the next entry with segments or one past
+        // the last segment index entry
+        let vector_index = merged.get_vector_index_cache().unwrap();
+        let ptrs: Vec<(u32, u32)> = vector_index
+            .chunks_exact(crate::VECTOR_INDEX_SIZE)
+            .map(|entry| (u32_at(entry, 0), u32_at(entry, 4)))
+            .filter(|(start_ptr, end_ptr)| start_ptr != end_ptr)
+            .collect();
+        assert_eq!(ptrs.len(), 256 + 1 + 2);
+        for pair in ptrs.windows(2) {
+            assert_eq!(pair[0].1, pair[1].0);
+        }
+        let info = merged.database_info();
+        assert_eq!(ptrs[0].0, info.start_index_ptr);
+        assert_eq!(
+            ptrs[ptrs.len() - 1].1,
+            info.end_index_ptr + crate::SEGMENT_INDEX_SIZE as u32
+        );
+    }
+
+    #[test]
+    fn test_merge_overlap() {
+        let region = "中国|0|福建省|福州市|电信";
+        let merged = Searcher::merge(vec![
+            shard(&[(0x0100_0000, 0x0100_03FF, region)]),
+            shard(&[(0x0100_0000, 0x0100_03FF, region)]),
+        ])
+        .unwrap();
+        assert_eq!(merged.segment_count(), 1);
+
+        let result = Searcher::merge(vec![
+            shard(&[(0x0100_0000, 0x0100_03FF, region)]),
+            shard(&[(0x0100_0200, 0x0100_02FF, "美国|0|0|0|0")]),
+        ]);
+        match result {
+            Err(Ip2RegionError::InvalidDatabase { reason }) => assert_eq!(
+                reason,
+                "the shards overlap, 1.0.0.0-1.0.3.255 of 中国|0|福建省|福州市|电信 \
+                 and 1.0.2.0-1.0.2.255 of 美国|0|0|0|0"
+            ),
+            _ => panic!("the overlapping shards are merged"),
+        }
+
+        assert!(matches!(
+            Searcher::merge(Vec::new()),
+            Err(Ip2RegionError::InvalidDatabase { .. })
+        ));
+    }
+}
@@ -0,0 +1,241 @@
+//! a searcher of a small memory footprint for the embedded devices, only the vector index of
+//! the first segments is held in memory and everything else is read from its own index file
+//!
+//! the index file is built from an ipv4 searcher by `Searcher::into_mini`, all the numbers are
+//! little endian
+//!
+//! | offset                     | length             | content                               |
+//! |----------------------------|--------------------|---------------------------------------|
+//! | 0                          | 8                  | the magic `ip2rmini`                  |
+//! | 8                          | 4                  | the segment count                     |
+//! | 12                         | 4 * 65537          | the first segment ending in every /16 |
+//! | `SEGMENTS_START`           | 14 * segment count | the segments like the xdb file        |
+//! | behind the segments        | the rest           | the distinct regions                  |
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+
+use crate::format::{ptr_at, u16_at, u32_at, SEGMENT_INDEX_SIZE};
+use crate::searcher::open_xdb_file;
+use crate::{Ip2RegionError, IpInfo, Searcher, ToUIntIP};
+
+const MAGIC: &[u8; 8] = b"ip2rmini";
+/// a /16 cell per entry and the segment count behind the last one
+const CELLS: usize = 1 << 16;
+const CELLS_START: usize = MAGIC.len() + 4;
+const SEGMENTS_START: usize = CELLS_START + (CELLS + 1) * 4;
+/// the segments of a cell are binary searched on the disk until they fit in one read
+const PAGE_SIZE: usize = 4096;
+
+/// search the index file built by `Searcher::into_mini`, a search seeks the disk 3 times or a
+/// few more in the densest cells, the memory is about 256 KB of the cells whatever the size of
+/// the database. only ipv4 is supported
+#[derive(Debug)]
+pub struct MiniSearcher {
+    /// the index of the first segment ending at or after the start of every /16 cell
+    cells: Box<[u32]>,
+    file: Mutex<File>,
+}
+
+impl MiniSearcher {
+    /// open an index file written by `Searcher::into_mini`
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Ip2RegionError> {
+        let path = path.as_ref();
+        let mut file = open_xdb_file(path)?;
+        let mut head = vec![0; SEGMENTS_START];
+        file.read_exact(&mut head).map_err(|_| invalid())?;
+        if &head[..MAGIC.len()] != MAGIC {
+            return Err(invalid());
+        }
+        let count = u32_at(&head, MAGIC.len());
+        let cells: Box<[u32]> = (0..=CELLS)
+            .map(|cell| u32_at(&head, CELLS_START + cell * 4))
+            .collect();
+        if cells.windows(2).any(|pair| pair[0] > pair[1]) || cells[CELLS] != count {
+            return Err(invalid());
+        }
+        let length = file.metadata()?.len();
+        if length < (SEGMENTS_START + count as usize * SEGMENT_INDEX_SIZE) as u64 {
+            return Err(invalid());
+        }
+        Ok(Self {
+            cells,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// the number of the segments in the index file
+    pub fn segment_count(&self) -> usize {
+        self.cells[CELLS] as usize
+    }
+
+    pub fn search<T>(&self, ip: T) -> Result<IpInfo, Ip2RegionError>
+    where
+        T: ToUIntIP,
+    {
+        let ip = ip.to_u32_ip()?;
+        let cell = (ip >> 16) as usize;
+        // the segment of the ip is the first one ending at or after it, at most the first one
+        // ending in the next cell, it is always in `left..right`
+        let mut left = self.cells[cell] as usize;
+        let mut right = (self.cells[cell + 1] as usize + 1).min(self.segment_count());
+
+        // the file is seeked before every read, so it is still usable after a panic
+        let mut file = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+        while (right - left) * SEGMENT_INDEX_SIZE > PAGE_SIZE {
+            let mid = (left + right) >> 1;
+            let entry = read_at(&mut file, segment_ptr(mid), SEGMENT_INDEX_SIZE)?;
+            if u32_at(&entry, 4) < ip {
+                left = mid + 1;
+            } else {
+                right = mid + 1;
+            }
+        }
+        let page = read_at(
+            &mut file,
+            segment_ptr(left),
+            (right - left) * SEGMENT_INDEX_SIZE,
+        )?;
+        let entry = page
+            .chunks_exact(SEGMENT_INDEX_SIZE)
+            .find(|entry| u32_at(entry, 4) >= ip)
+            .filter(|entry| u32_at(entry, 0) <= ip)
+            .ok_or(Ip2RegionError::NotMatched)?;
+        let region = read_at(&mut file, ptr_at(entry, 10), usize::from(u16_at(entry, 8)))?;
+        IpInfo::try_from(String::from_utf8(region)?)
+    }
+}
+
+impl crate::Search for MiniSearcher {
+    fn search(&self, ip: u32) -> Result<IpInfo, Ip2RegionError> {
+        MiniSearcher::search(self, ip)
+    }
+}
+
+impl Searcher {
+    /// write the index file of `MiniSearcher` to `path` and open it, for the devices which can
+    /// not afford the memory of a searcher. it is built once where the memory is enough and
+    /// shipped to the devices, only ipv4 xdb files are supported like `iter_segments`
+    pub fn into_mini<P: AsRef<Path>>(self, path: P) -> Result<MiniSearcher, Ip2RegionError> {
+        let segments = self.iter_segments().collect::<Result<Vec<_>, _>>()?;
+        drop(self);
+
+        let mut cells = Vec::with_capacity((CELLS + 1) * 4);
+        let mut index = 0;
+        for cell in 0..CELLS {
+            let cell_start = (cell as u32) << 16;
+            while index < segments.len() && segments[index].end_ip < cell_start {
+                index += 1;
+            }
+            cells.extend_from_slice(&(index as u32).to_le_bytes());
+        }
+        cells.extend_from_slice(&(segments.len() as u32).to_le_bytes());
+
+        let regions_start = SEGMENTS_START + segments.len() * SEGMENT_INDEX_SIZE;
+        let mut entries = Vec::with_capacity(segments.len() * SEGMENT_INDEX_SIZE);
+        let mut regions = Vec::new();
+        let mut region_offsets = HashMap::new();
+        for segment in &segments {
+            let region = segment.info.to_string();
+            let length =
+                u16::try_from(region.len()).map_err(|_| Ip2RegionError::InvalidDatabase {
+                    reason: format!("the region {region} is too long"),
+                })?;
+            let offset = *region_offsets.entry(region).or_insert_with_key(|region| {
+                let offset = regions_start + regions.len();
+                regions.extend_from_slice(region.as_bytes());
+                offset as u32
+            });
+            entries.extend_from_slice(&segment.start_ip.to_le_bytes());
+            entries.extend_from_slice(&segment.end_ip.to_le_bytes());
+            entries.extend_from_slice(&length.to_le_bytes());
+            entries.extend_from_slice(&offset.to_le_bytes());
+        }
+
+        let mut bytes = Vec::with_capacity(regions_start + regions.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&(segments.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&cells);
+        bytes.extend_from_slice(&entries);
+        bytes.extend_from_slice(&regions);
+        fs::write(path.as_ref(), bytes)?;
+        MiniSearcher::open(path)
+    }
+}
+
+fn segment_ptr(index: usize) -> usize {
+    SEGMENTS_START + index * SEGMENT_INDEX_SIZE
+}
+
+fn read_at(file: &mut File, ptr: usize, length: usize) -> Result<Vec<u8>, Ip2RegionError> {
+    let mut buffer = vec![0; length];
+    file.seek(SeekFrom::Start(ptr as u64))?;
+    file.read_exact(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn invalid() -> Ip2RegionError {
+    Ip2RegionError::InvalidDatabase {
+        reason: "not a mini searcher index file".to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::searcher::default_detect_xdb_file;
+
+    use super::*;
+
+    #[test]
+    fn test_into_mini() {
+        let segments = [
+            (0x0100_0000u32, 0x0100_00FF, "澳大利亚|0|0|0|0"),
+            (0x0100_0200, 0x0102_FFFF, "中国|0|福建省|福州市|电信"),
+            (0x0103_0000, 0x0103_00FF, "澳大利亚|0|0|0|0"),
+        ];
+        let searcher = Searcher::from_bytes(crate::maker::make_xdb(&segments)).unwrap();
+        let path = std::env::temp_dir().join("xdb_test_into_mini.bin");
+        let mini = searcher.into_mini(&path).unwrap();
+
+        assert_eq!(mini.search("1.0.0.1").unwrap().country, "澳大利亚");
+        // a segment crossing the cells
+        assert_eq!(mini.search("1.1.128.0").unwrap().city, "福州市");
+        assert_eq!(mini.search("1.2.255.255").unwrap().city, "福州市");
+        assert_eq!(mini.search("1.3.0.255").unwrap().country, "澳大利亚");
+        for ip in ["0.0.0.0", "1.0.1.0", "1.3.1.0", "255.255.255.255"] {
+            assert!(matches!(mini.search(ip), Err(Ip2RegionError::NotMatched)));
+        }
+        // the regions are stored once
+        let length = fs::metadata(&path).unwrap().len() as usize;
+        let regions = "澳大利亚|0|0|0|0中国|0|福建省|福州市|电信".len();
+        assert_eq!(length, segment_ptr(mini.segment_count()) + regions);
+
+        fs::write(&path, b"ip2region").unwrap();
+        assert!(matches!(
+            MiniSearcher::open(&path),
+            Err(Ip2RegionError::InvalidDatabase { .. })
+        ));
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            MiniSearcher::open(&path),
+            Err(Ip2RegionError::FileNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_into_mini_matches_searcher() {
+        let searcher = Searcher::new(default_detect_xdb_file().unwrap()).unwrap();
+        let path = std::env::temp_dir().join("xdb_test_into_mini_xdb.bin");
+        let mini = Searcher::new(default_detect_xdb_file().unwrap())
+            .unwrap()
+            .into_mini(&path)
+            .unwrap();
+        assert_eq!(mini.segment_count(), searcher.segment_count());
+        for ip in (0..=u32::MAX).step_by(9973) {
+            assert_eq!(mini.search(ip).unwrap(), searcher.search(ip).unwrap());
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,49 @@
+use rayon::prelude::*;
+
+use crate::searcher::global_searcher;
+use crate::{Ip2RegionError, IpInfo, Searcher};
+
+impl Searcher {
+    /// search a batch of ips on the rayon thread pool, the results are in the order of `ips`.
+    ///
+    /// it only scales with `CachePolicy::Full` and `CachePolicy::Mmap`, the searches of the
+    /// other cache policies wait for each other on the shared reader or open the xdb file once
+    /// per ip, use `search_many` for them
+    pub fn parallel_search(&self, ips: &[u32]) -> Vec<Result<IpInfo, Ip2RegionError>> {
+        ips.par_iter().map(|&ip| self.search(ip)).collect()
+    }
+}
+
+/// search a batch of ips in parallel by the searcher initialized in `searcher_init`, the outer
+/// error is the one of initializing it like `search_by_ip`
+pub fn parallel_search(ips: &[u32]) -> Result<Vec<Result<IpInfo, Ip2RegionError>>, Ip2RegionError> {
+    Ok(global_searcher()?.parallel_search(ips))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::searcher::default_detect_xdb_file;
+    use crate::searcher_init;
+
+    use super::*;
+
+    #[test]
+    fn test_parallel_search() {
+        let searcher = Searcher::new(default_detect_xdb_file().unwrap()).unwrap();
+        let ips: Vec<u32> = (0..u32::MAX).step_by(997_331).collect();
+        let results = searcher.parallel_search(&ips);
+        assert_eq!(results.len(), ips.len());
+        for (ip, result) in ips.iter().zip(results) {
+            assert_eq!(result.unwrap(), searcher.search(*ip).unwrap());
+        }
+
+        searcher_init(None).unwrap();
+        assert_eq!(
+            parallel_search(&[u32::from_be_bytes([1, 0, 1, 0])]).unwrap()[0]
+                .as_ref()
+                .unwrap()
+                .raw(),
+            "中国|0|福建省|福州市|电信"
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use std::ops::Deref;
+use std::sync::Arc;
+
+use crate::Searcher;
+
+/// a handle sharing one `Searcher` between the tasks of an async server, it is only a named
+/// `Arc<Searcher>`: the searcher is `Sync` and every search takes `&self`, so there is no
+/// pool of searchers to check out and `get` never waits
+///
+/// ```ignore
+/// use axum::{extract::Path, routing::get, Extension, Router};
+/// use xdb::{Searcher, SearcherPool};
+///
+/// async fn region(Extension(pool): Extension<SearcherPool>, Path(ip): Path<String>) -> String {
+///     pool.get().search(ip.as_str()).map_or_else(|e| e.to_string(), |info| info.raw())
+/// }
+///
+/// let pool = SearcherPool::new(Searcher::new("/data/ip2region.xdb").unwrap());
+/// let app: Router = Router::new()
+///     .route("/region/:ip", get(region))
+///     .layer(Extension(pool));
+/// ```
+#[derive(Clone)]
+pub struct SearcherPool {
+    searcher: Arc<Searcher>,
+}
+
+/// the searcher handed out by `SearcherPool::get`, cloning it only bumps a reference count
+#[derive(Clone)]
+pub struct PooledSearcher {
+    searcher: Arc<Searcher>,
+}
+
+impl SearcherPool {
+    pub fn new(searcher: Searcher) -> Self {
+        Self::from(Arc::new(searcher))
+    }
+
+    /// the shared searcher, it can be kept across `.await` points
+    pub fn get(&self) -> PooledSearcher {
+        PooledSearcher {
+            searcher: Arc::clone(&self.searcher),
+        }
+    }
+}
+
+impl From<Searcher> for SearcherPool {
+    fn from(searcher: Searcher) -> Self {
+        Self::new(searcher)
+    }
+}
+
+impl From<Arc<Searcher>> for SearcherPool {
+    fn from(searcher: Arc<Searcher>) -> Self {
+        Self { searcher }
+    }
+}
+
+impl Deref for PooledSearcher {
+    type Target = Searcher;
+
+    fn deref(&self) -> &Searcher {
+        &self.searcher
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use crate::searcher::default_detect_xdb_file;
+
+    use super::*;
+
+    #[test]
+    fn test_pool_shares_searcher() {
+        let searcher = Arc::new(Searcher::new(default_detect_xdb_file().unwrap()).unwrap());
+        let pool = SearcherPool::from(Arc::clone(&searcher));
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let pool = pool.clone();
+                thread::spawn(move || pool.get().search("1.0.1.0").unwrap())
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), searcher.search("1.0.1.0").unwrap());
+        }
+        assert!(std::ptr::eq(&*pool.get(), &*searcher));
+    }
+}
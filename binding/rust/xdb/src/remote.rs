@@ -0,0 +1,92 @@
+//! download the whole xdb file over http, for the deployments keeping it in an object storage
+//! like s3 instead of the local file system
+use crate::{Ip2RegionError, Searcher};
+
+impl Searcher {
+    /// download the xdb file at `url` and search it in memory, there is no file to read
+    /// from later so the searcher always uses `CachePolicy::Full`, the downloaded bytes are
+    /// validated like `from_bytes`.
+    ///
+    /// it blocks the current thread and panics inside an async runtime, use
+    /// `from_url_async` there
+    pub fn from_url(url: &str) -> Result<Self, Ip2RegionError> {
+        let response = reqwest::blocking::get(url)?.error_for_status()?;
+        Self::from_bytes(response.bytes()?.to_vec())
+    }
+
+    /// same as `from_url` but downloaded by the async `reqwest::Client`,
+    /// it must run inside a tokio runtime
+    pub async fn from_url_async(url: &str) -> Result<Self, Ip2RegionError> {
+        let response = reqwest::get(url).await?.error_for_status()?;
+        Self::from_bytes(response.bytes().await?.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use crate::searcher::default_detect_xdb_file;
+    use crate::CachePolicy;
+
+    use super::*;
+
+    /// answer the first request on a local port with `status` and `body`, returns the url
+    fn serve_once(status: &'static str, body: Vec<u8>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/ip2region.xdb", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            let mut stream = reader.into_inner();
+            write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(&body).unwrap();
+        });
+        url
+    }
+
+    #[test]
+    fn test_from_url() {
+        let buffer = std::fs::read(default_detect_xdb_file().unwrap()).unwrap();
+        let searcher = Searcher::from_url(&serve_once("200 OK", buffer)).unwrap();
+        assert_eq!(searcher.cache_policy(), CachePolicy::Full);
+        assert_eq!(
+            searcher.search("1.0.1.0").unwrap().raw(),
+            "中国|0|福建省|福州市|电信"
+        );
+
+        let url = serve_once("404 Not Found", Vec::new());
+        assert!(matches!(
+            Searcher::from_url(&url),
+            Err(Ip2RegionError::Http(_))
+        ));
+        let url = serve_once("200 OK", b"not a xdb file".to_vec());
+        assert!(matches!(
+            Searcher::from_url(&url),
+            Err(Ip2RegionError::InvalidDatabase { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_from_url_async() {
+        let buffer = std::fs::read(default_detect_xdb_file().unwrap()).unwrap();
+        let searcher = Searcher::from_url_async(&serve_once("200 OK", buffer))
+            .await
+            .unwrap();
+        assert_eq!(
+            searcher.search("1.0.1.0").unwrap().raw(),
+            "中国|0|福建省|福州市|电信"
+        );
+    }
+}
@@ -1,62 +1,1061 @@
-use std::error::Error;
-use std::fmt::Display;
+use std::collections::HashMap;
+use std::fmt::{self, Display};
 use std::fs::File;
-use std::io::Read;
-use std::path::Path;
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs};
+use std::ops::{Deref, Range};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
 
-use once_cell::sync::OnceCell;
+use arc_swap::{ArcSwap, Guard};
 
-use crate::ToUIntIP;
+use crate::checksum::{check_checksum, stored_checksum, Crc32};
+use crate::format::{
+    check_header, get_start_end_ptr, index_ptrs, last_segment, ptr_at, search_in_buffer, too_short,
+    u16_at, u32_at, vector_index_length, BinarySearchSpan, SegmentIp, HEADER_INFO_LENGTH,
+    IPV6_SEGMENT_INDEX_SIZE, VECTOR_INDEX_SIZE,
+};
+pub use crate::format::{get_block_by_size, validate_header, IpVersion};
+use crate::{CachePolicy, Ip2RegionError, IpInfo, SearchResult, ToUInt128IP, ToUIntIP};
 
-const HEADER_INFO_LENGTH: usize = 256;
-const VECTOR_INDEX_COLS: usize = 256;
-const VECTOR_INDEX_SIZE: usize = 8;
-const SEGMENT_INDEX_SIZE: usize = 14;
-const VECTOR_INDEX_LENGTH: usize = 512 * 1024;
+/// the searcher behind `searcher_init` and `search_by_ip`
+static SEARCHER: OnceLock<Searcher> = OnceLock::new();
+/// held while `SEARCHER` is loaded, so the xdb file is loaded once by the racing inits
+static SEARCHER_INIT: Mutex<()> = Mutex::new(());
 
-const XDB_FILEPATH_ENV: &str = "XDB_FILEPATH";
+/// a xdb searcher owns its cached part of the xdb file, it is `Send + Sync` and designed to be
+/// shared between threads by `Arc<Searcher>`,
+/// `reload` swaps in a new xdb file while the searches in flight finish against the old one
+pub struct Searcher {
+    xdb: ArcSwap<Xdb>,
+}
 
-static CACHE: OnceCell<Vec<u8>> = OnceCell::new();
+/// a loaded xdb file, it is read only and replaced as a whole by `reload`
+pub(crate) struct Xdb {
+    cache: Cache,
+    ip_version: IpVersion,
+    header: [u8; HEADER_INFO_LENGTH],
+    /// fixed by the index policy of the header
+    vector_index_length: usize,
+}
 
-/// check https://mp.weixin.qq.com/s/ndjzu0BgaeBmDOCw5aqHUg for details
-pub fn search_by_ip<T>(ip: T) -> Result<String, Box<dyn Error>>
-where
-    T: ToUIntIP + Display,
-{
-    let ip = ip.to_u32_ip()?;
-    let il0 = ((ip >> 24) & 0xFF) as usize;
-    let il1 = ((ip >> 16) & 0xFF) as usize;
-    let idx = VECTOR_INDEX_SIZE * (il0 * VECTOR_INDEX_COLS + il1);
-    let start_point = idx;
-    let vector_cache = get_vector_index_cache();
-    let start_ptr = get_block_by_size(vector_cache, start_point, 4);
-    let end_ptr = get_block_by_size(vector_cache, start_point + 4, 4);
+enum Cache {
+    /// the whole xdb file, on the heap or mapped into memory
+    Full(Arc<Buffer>),
+    VecIndex {
+        vector_index: Arc<Buffer>,
+        reader: Mutex<Box<dyn ReadSeek>>,
+    },
+    /// the vector index and the first `bytes` after it, the reader is a `SegmentedReader`
+    Segmented {
+        vector_index: Arc<Buffer>,
+        segments: Arc<Buffer>,
+        bytes: usize,
+        reader: Mutex<Box<dyn ReadSeek>>,
+    },
+    Never(Source),
+}
+
+/// the memory a cached part of the xdb file lives in
+enum Buffer {
+    Heap(Vec<u8>),
+    #[cfg(feature = "memmap2")]
+    Mmap(memmap2::Mmap),
+}
+
+impl Deref for Buffer {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        match self {
+            Buffer::Heap(buffer) => buffer,
+            #[cfg(feature = "memmap2")]
+            Buffer::Mmap(mmap) => mmap,
+        }
+    }
+}
+
+/// the reader of `CachePolicy::Segmented`, the bytes cached after the vector index are read from
+/// memory and the others from the xdb file
+struct SegmentedReader<R> {
+    segments_start: u64,
+    segments: Arc<Buffer>,
+    inner: R,
+    pos: u64,
+}
+
+impl<R: Read + Seek> Read for SegmentedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let cached = self
+            .pos
+            .checked_sub(self.segments_start)
+            .and_then(|offset| self.segments.get(usize::try_from(offset).ok()?..))
+            .filter(|cached| !cached.is_empty());
+        let n = match cached {
+            Some(cached) => {
+                let n = cached.len().min(buf.len());
+                buf[..n].copy_from_slice(&cached[..n]);
+                n
+            }
+            None => {
+                // stop at the cached bytes so they are read from memory
+                let buf = match self.segments_start.checked_sub(self.pos) {
+                    Some(before) if before > 0 && before < buf.len() as u64 => {
+                        &mut buf[..before as usize]
+                    }
+                    _ => buf,
+                };
+                self.inner.seek(SeekFrom::Start(self.pos))?;
+                self.inner.read(buf)?
+            }
+        };
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Seek> Seek for SegmentedReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.pos = match pos {
+            SeekFrom::Start(pos) => pos,
+            SeekFrom::End(_) => self.inner.seek(pos)?,
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset).ok_or_else(|| {
+                io::Error::new(ErrorKind::InvalidInput, "seek to a negative position")
+            })?,
+        };
+        Ok(self.pos)
+    }
+}
+
+/// where `CachePolicy::Never` reads the xdb file from
+enum Source {
+    /// open the xdb file on every search
+    Path(PathBuf),
+    Reader(Mutex<Box<dyn ReadSeek>>),
+}
+
+/// the readers a `Searcher` can read the xdb file from
+pub trait ReadSeek: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> ReadSeek for T {}
+
+/// the metadata in the 256 bytes header of a xdb file, all numbers are little-endian:
+///
+/// | offset | length | field                                                    |
+/// |--------|--------|----------------------------------------------------------|
+/// | 0      | 2      | `version`, the xdb structure version                     |
+/// | 2      | 2      | the index policy, always 1                               |
+/// | 4      | 4      | `build_at`, the unix timestamp in seconds                |
+/// | 8      | 4      | `start_index_ptr`, the first segment index entry         |
+/// | 12     | 4      | `end_index_ptr`, the last segment index entry            |
+/// | 16     | 2      | the ip version, 4 or 6, only for the structure version 3 |
+/// | 18     | 2      | the bytes of a pointer, only for the structure version 3 |
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DatabaseInfo {
+    pub version: u32,
+    pub build_at: u64,
+    pub start_index_ptr: u32,
+    pub end_index_ptr: u32,
+    /// the entries of the segment index, one per ip range
+    pub total_segments: u32,
+}
+
+/// bytes of the cached xdb buffer, they keep the buffer alive after `Searcher::reload`
+#[derive(Clone)]
+pub struct CachedBytes {
+    buffer: Arc<Buffer>,
+    range: Range<usize>,
+}
+
+impl Deref for CachedBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.buffer[self.range.clone()]
+    }
+}
+
+impl AsRef<[u8]> for CachedBytes {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl fmt::Debug for CachedBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// a clone shares the loaded xdb file with the original instead of copying its cache, so it
+/// is cheap even for `CachePolicy::Full`, the readers of `CachePolicy::VecIndex` and
+/// `CachePolicy::Never` are shared behind their lock too. `reload` only swaps the xdb file of
+/// the searcher it is called on, the shared one is freed when the last clone moves off it
+impl Clone for Searcher {
+    fn clone(&self) -> Self {
+        Self {
+            xdb: ArcSwap::new(self.xdb.load_full()),
+        }
+    }
+}
+
+/// a utf-8 checked part of the cached xdb buffer, like `CachedBytes`
+#[derive(Clone)]
+pub struct CachedStr(CachedBytes);
+
+impl CachedStr {
+    fn new(bytes: CachedBytes) -> Result<Self, Ip2RegionError> {
+        if let Err(e) = std::str::from_utf8(&bytes) {
+            return Err(Ip2RegionError::InvalidDatabase {
+                reason: format!("invalid utf-8 region data: {e}"),
+            });
+        }
+        Ok(Self(bytes))
+    }
+}
+
+impl Deref for CachedStr {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        // SAFETY: the bytes are checked in `CachedStr::new` and the buffer is read only
+        unsafe { std::str::from_utf8_unchecked(&self.0) }
+    }
+}
+
+impl AsRef<str> for CachedStr {
+    fn as_ref(&self) -> &str {
+        self
+    }
+}
+
+impl fmt::Debug for CachedStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl Display for CachedStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self)
+    }
+}
+
+/// the page size `preload` and `warm_cache` touch the cached buffer by
+const PAGE_SIZE: usize = 4096;
+
+impl Searcher {
+    /// load the whole xdb file at `xdb_filepath` into memory
+    pub fn new<P: AsRef<Path>>(xdb_filepath: P) -> Result<Self, Ip2RegionError> {
+        Self::with_cache_policy(xdb_filepath, CachePolicy::Full)
+    }
+
+    /// open the xdb file at `xdb_filepath` and cache it as `cache_policy` describes
+    pub fn with_cache_policy<P: AsRef<Path>>(
+        xdb_filepath: P,
+        cache_policy: CachePolicy,
+    ) -> Result<Self, Ip2RegionError> {
+        Xdb::open(xdb_filepath.as_ref(), cache_policy).map(Self::from_xdb)
+    }
+
+    /// read the xdb file from any `Read + Seek`, like a file in an archive or a custom
+    /// virtual filesystem, the reader is kept by the searcher unless `CachePolicy::Full`
+    pub fn from_reader<R>(reader: R, cache_policy: CachePolicy) -> Result<Self, Ip2RegionError>
+    where
+        R: Read + Seek + Send + 'static,
+    {
+        Xdb::from_reader(reader, cache_policy).map(Self::from_xdb)
+    }
+
+    /// create a `CachePolicy::Full` searcher from the whole xdb file content already in memory,
+    /// for example embedded by `include_bytes!` or downloaded at runtime
+    pub fn from_bytes(buffer: Vec<u8>) -> Result<Self, Ip2RegionError> {
+        Xdb::from_bytes(buffer).map(Self::from_xdb)
+    }
+
+    /// map the xdb file at `xdb_filepath` into memory, the pages are loaded by the os on the
+    /// first touch, so it starts faster and uses less memory than `CachePolicy::Full`.
+    ///
+    /// the file must not be modified in place while it is mapped, replace it by a rename
+    /// and `reload` instead
+    #[cfg(feature = "memmap2")]
+    pub fn from_mmap(xdb_filepath: &Path) -> Result<Self, Ip2RegionError> {
+        Xdb::from_mmap(xdb_filepath).map(Self::from_xdb)
+    }
+
+    fn from_xdb(xdb: Xdb) -> Self {
+        Self {
+            xdb: ArcSwap::from_pointee(xdb),
+        }
+    }
+
+    /// the current xdb file, hold it for the whole search so a `reload` does not mix two files
+    pub(crate) fn xdb(&self) -> Guard<Arc<Xdb>> {
+        self.xdb.load()
+    }
+
+    /// load the xdb file at `xdb_filepath` with the current cache policy and swap it in,
+    /// the searcher is left untouched if the new file can not be loaded or is invalid.
+    ///
+    /// the old and the new cache are both in memory until the searches in flight and the
+    /// `CachedBytes` of the old one are dropped, so `CachePolicy::Full` needs twice the size
+    /// of the xdb file for a moment
+    pub fn reload<P: AsRef<Path>>(&self, xdb_filepath: P) -> Result<(), Ip2RegionError> {
+        let xdb = Xdb::open(xdb_filepath.as_ref(), self.cache_policy())?;
+        self.xdb.store(Arc::new(xdb));
+        Ok(())
+    }
+
+    pub fn cache_policy(&self) -> CachePolicy {
+        match self.xdb.load().cache {
+            Cache::Full(ref buffer) => match **buffer {
+                Buffer::Heap(_) => CachePolicy::Full,
+                #[cfg(feature = "memmap2")]
+                Buffer::Mmap(_) => CachePolicy::Mmap,
+            },
+            Cache::VecIndex { .. } => CachePolicy::VecIndex,
+            Cache::Segmented { bytes, .. } => CachePolicy::Segmented(bytes),
+            Cache::Never(_) => CachePolicy::Never,
+        }
+    }
+
+    /// the version of the ips stored in the xdb file
+    pub fn ip_version(&self) -> IpVersion {
+        self.xdb.load().ip_version
+    }
+
+    /// the xdb structure version, the little endian u16 at offset 0 of the header,
+    /// 2 for the ipv4 only files and 3 for the dual-stack files
+    pub fn version(&self) -> u32 {
+        u32::from(u16_at(&self.xdb.load().header, 0))
+    }
+
+    /// the unix timestamp in seconds the xdb file was built at,
+    /// the little endian u32 at offset 4 of the header
+    pub fn build_at(&self) -> u64 {
+        u64::from(u32_at(&self.xdb.load().header, 4))
+    }
+
+    /// all the header fields of the xdb file, to compare the files of a fleet at once
+    pub fn database_info(&self) -> DatabaseInfo {
+        let xdb = self.xdb.load();
+        DatabaseInfo {
+            version: u32::from(u16_at(&xdb.header, 0)),
+            build_at: u64::from(u32_at(&xdb.header, 4)),
+            start_index_ptr: u32_at(&xdb.header, 8),
+            end_index_ptr: u32_at(&xdb.header, 12),
+            total_segments: xdb.segment_count() as u32,
+        }
+    }
+
+    /// check https://mp.weixin.qq.com/s/ndjzu0BgaeBmDOCw5aqHUg for details,
+    /// every search is a `search` debug span with the resolved `ip` field
+    #[tracing::instrument(level = "debug", skip_all, fields(ip = tracing::field::Empty))]
+    pub fn search<T>(&self, ip: T) -> Result<IpInfo, Ip2RegionError>
+    where
+        T: ToUIntIP,
+    {
+        let ip = ip.to_u32_ip()?;
+        tracing::Span::current().record("ip", tracing::field::display(Ipv4Addr::from(ip)));
+        self.search_and_parse(ip)
+    }
+
+    /// search and parse the fields in one step, for `CachePolicy::Full` the fields are parsed
+    /// from the cached buffer in place without the intermediate `String` of the whole region,
+    /// `search` is the same without its tracing span
+    pub fn search_and_parse<T>(&self, ip: T) -> Result<IpInfo, Ip2RegionError>
+    where
+        T: ToUIntIP,
+    {
+        let ip = ip.to_u32_ip()?;
+        let xdb = self.xdb.load();
+        match &xdb.cache {
+            Cache::Full(buffer) if xdb.ip_version == IpVersion::V4 => {
+                let region = &buffer[search_in_buffer(buffer, xdb.vector_index_length, ip)?];
+                match std::str::from_utf8(region) {
+                    Ok(region) => IpInfo::from_region(region),
+                    // only the error allocates, for the same error as the other policies
+                    Err(_) => Err(String::from_utf8(region.to_vec()).unwrap_err().into()),
+                }
+            }
+            _ => IpInfo::try_from(xdb.search_region(ip)?),
+        }
+    }
+
+    /// same as `search`, but an ip not covered by any segment is `Ok(None)` like
+    /// `HashMap::get`, the errors are left for the invalid ips and the broken xdb files
+    pub fn search_opt<T>(&self, ip: T) -> Result<Option<IpInfo>, Ip2RegionError>
+    where
+        T: ToUIntIP,
+    {
+        match self.search(ip) {
+            Ok(info) => Ok(Some(info)),
+            Err(Ip2RegionError::NotMatched) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// search a dotted-decimal ipv4 string, unlike `search("...")` the decimal u32 form is not
+    /// accepted, the string is parsed in place and only the error allocates
+    pub fn search_str(&self, ip: &str) -> Result<IpInfo, Ip2RegionError> {
+        let ip = Ipv4Addr::from_str(ip).map_err(|_| Ip2RegionError::InvalidIp(ip.to_owned()))?;
+        self.search(ip)
+    }
+
+    /// resolve `hostname` and search its first address of the ip version of the xdb file,
+    /// an ip string is searched as it is.
+    ///
+    /// it is a blocking dns lookup by the system resolver which may take seconds, use
+    /// `search_hostname_async` of the `async` feature in the services instead
+    pub fn search_hostname(&self, hostname: &str) -> Result<IpInfo, Ip2RegionError> {
+        self.search_resolved((hostname, 0).to_socket_addrs()?)
+    }
+
+    /// search the first of the resolved `addrs` of the ip version of the xdb file
+    pub(crate) fn search_resolved(
+        &self,
+        mut addrs: impl Iterator<Item = SocketAddr>,
+    ) -> Result<IpInfo, Ip2RegionError> {
+        let ip_version = self.ip_version();
+        match addrs.find(|addr| (ip_version == IpVersion::V4) == addr.is_ipv4()) {
+            Some(SocketAddr::V4(addr)) => self.search(*addr.ip()),
+            Some(SocketAddr::V6(addr)) => self.search_by_ipv6(*addr.ip()),
+            None => Err(Ip2RegionError::IpVersionMismatch),
+        }
+    }
+
+    /// same as `search` but the region string is returned as it is, without splitting the
+    /// fields into an `IpInfo`
+    pub fn search_result<T>(&self, ip: T) -> Result<SearchResult, Ip2RegionError>
+    where
+        T: ToUIntIP,
+    {
+        SearchResult::try_from(self.xdb.load().search_region(ip.to_u32_ip()?)?)
+    }
+
+    /// the `IpInfo` of the ip as a json object with the keys `country`, `region`, `province`,
+    /// `city` and `isp`, for the responses of the http handlers
+    #[cfg(feature = "serde")]
+    pub fn search_as_json<T>(&self, ip: T) -> Result<String, Ip2RegionError>
+    where
+        T: ToUIntIP,
+    {
+        let info = self.search(ip)?;
+        Ok(serde_json::to_string(&info).map_err(std::io::Error::from)?)
+    }
+
+    /// the fields by their names `country`, `region`, `province`, `city` and `isp` with the
+    /// owned keys, for the scripting language bindings converting the result to their own map,
+    /// `HashMap<&'static str, String>::from(info)` avoids allocating the keys
+    pub fn search_as_map<T>(&self, ip: T) -> Result<HashMap<String, String>, Ip2RegionError>
+    where
+        T: ToUIntIP,
+    {
+        let fields = HashMap::<&'static str, String>::from(self.search(ip)?);
+        Ok(fields
+            .into_iter()
+            .map(|(name, field)| (name.to_owned(), field))
+            .collect())
+    }
+
+    /// search an ipv6 address, only works for the dual-stack xdb file built with ipv6 segments,
+    /// it is converted by `ToUInt128IP` and searched by `search_u128`
+    pub fn search_by_ipv6<T>(&self, ip: T) -> Result<IpInfo, Ip2RegionError>
+    where
+        T: ToUInt128IP,
+    {
+        self.search_u128(ip.to_u128_ip()?)
+    }
+
+    /// search an ipv6 address as the u128 of its 16 octets in network byte order, the
+    /// big-endian `u128::from(Ipv6Addr)`, it is the `search` of the ipv6 xdb files
+    #[tracing::instrument(level = "debug", skip_all, fields(ip = %Ipv6Addr::from(ip)))]
+    pub fn search_u128(&self, ip: u128) -> Result<IpInfo, Ip2RegionError> {
+        IpInfo::try_from(self.xdb.load().search_region(ip)?)
+    }
+
+    /// the raw region bytes in the cached buffer without copying them, only available for
+    /// `CachePolicy::Full`. `raw_segment_bytes` is the canonical name of it, this one is kept
+    /// for the existing callers
+    pub fn search_raw(&self, ip: u32) -> Result<CachedBytes, Ip2RegionError> {
+        self.raw_segment_bytes(ip)
+    }
+
+    /// the region string of the ip in the cached buffer, without the 14 bytes of its segment
+    /// index entry and without copying it, only available for `CachePolicy::Full`. the bytes
+    /// are neither checked as utf-8 nor split into the fields, for the ffi callers parsing the
+    /// `|` separated region themselves. it is the canonical name of `search_raw`.
+    ///
+    /// it returns `CachedBytes` dereferencing to `&[u8]` instead of a `&[u8]` borrowing the
+    /// searcher, because the buffer must outlive a `reload` which may free it while the slice
+    /// is still in use, the bytes keep it alive. `BorrowedSearcher::search_raw` is a plain
+    /// `&[u8]` into a buffer the caller owns
+    pub fn raw_segment_bytes(&self, ip: u32) -> Result<CachedBytes, Ip2RegionError> {
+        let xdb = self.xdb.load();
+        if xdb.ip_version != IpVersion::V4 {
+            return Err(Ip2RegionError::IpVersionMismatch);
+        }
+        match &xdb.cache {
+            Cache::Full(buffer) => Ok(CachedBytes {
+                range: search_in_buffer(buffer, xdb.vector_index_length, ip)?,
+                buffer: Arc::clone(buffer),
+            }),
+            _ => Err(Ip2RegionError::UnsupportedPolicy(self.cache_policy())),
+        }
+    }
+
+    /// only the country, the first field of the region, in the cached buffer without parsing
+    /// the other fields or allocating, for the geo restrictions checking every request.
+    /// it is the name stored in the xdb file like `中国`, not an iso code,
+    /// only available for `CachePolicy::Full`
+    pub fn country<T>(&self, ip: T) -> Result<CachedStr, Ip2RegionError>
+    where
+        T: ToUIntIP,
+    {
+        let mut region = self.raw_segment_bytes(ip.to_u32_ip()?)?;
+        let length = region
+            .iter()
+            .position(|&b| b == b'|')
+            .unwrap_or(region.len());
+        region.range.end = region.range.start + length;
+        CachedStr::new(region)
+    }
+
+    /// the autonomous system number of the ip, `None` if the isp field has none,
+    /// see `IpInfo::asn`
+    pub fn lookup_asn<T>(&self, ip: T) -> Result<Option<u32>, Ip2RegionError>
+    where
+        T: ToUIntIP,
+    {
+        Ok(self.search(ip)?.asn())
+    }
+
+    /// whether the ip is in mainland china, see `IpInfo::is_chinese_mainland`
+    pub fn is_chinese_mainland<T>(&self, ip: T) -> Result<bool, Ip2RegionError>
+    where
+        T: ToUIntIP,
+    {
+        Ok(self.search(ip)?.is_chinese_mainland())
+    }
+
+    /// the xdb filepath read on every search and its ip version, only for `CachePolicy::Never`
+    #[cfg(feature = "async")]
+    pub(crate) fn never_filepath(&self) -> Option<(PathBuf, IpVersion)> {
+        let xdb = self.xdb.load();
+        match &xdb.cache {
+            Cache::Never(Source::Path(xdb_filepath)) => {
+                Some((xdb_filepath.clone(), xdb.ip_version))
+            }
+            _ => None,
+        }
+    }
+
+    /// search a batch of ips, the vector index lock of `CachePolicy::VecIndex` is taken once
+    /// and the xdb file of `CachePolicy::Never` is opened once for the whole batch
+    pub fn search_many(&self, ips: &[u32]) -> Vec<Result<IpInfo, Ip2RegionError>> {
+        let xdb = self.xdb.load();
+        if xdb.ip_version != IpVersion::V4 {
+            return ips
+                .iter()
+                .map(|_| Err(Ip2RegionError::IpVersionMismatch))
+                .collect();
+        }
+        let regions: Vec<Result<String, Ip2RegionError>> = match &xdb.cache {
+            Cache::Full(buffer) => ips
+                .iter()
+                .map(|&ip| {
+                    Ok(String::from_utf8(
+                        buffer[search_in_buffer(buffer, xdb.vector_index_length, ip)?].to_vec(),
+                    )?)
+                })
+                .collect(),
+            Cache::VecIndex {
+                vector_index,
+                reader,
+            }
+            | Cache::Segmented {
+                vector_index,
+                reader,
+                ..
+            } => {
+                let mut reader = lock_reader(reader);
+                ips.iter()
+                    .map(|&ip| search_in_reader(&mut **reader, Some(vector_index), ip))
+                    .collect()
+            }
+            Cache::Never(Source::Path(xdb_filepath)) => match open_xdb_file(xdb_filepath) {
+                Ok(mut file) => ips
+                    .iter()
+                    .map(|&ip| search_in_reader(&mut file, None, ip))
+                    .collect(),
+                // every search reports its own open error
+                Err(_) => ips.iter().map(|&ip| xdb.search_region(ip)).collect(),
+            },
+            Cache::Never(Source::Reader(reader)) => {
+                let mut reader = lock_reader(reader);
+                ips.iter()
+                    .map(|&ip| search_in_reader(&mut **reader, None, ip))
+                    .collect()
+            }
+        };
+        regions
+            .into_iter()
+            .map(|region| IpInfo::try_from(region?))
+            .collect()
+    }
+
+    /// search the ips lazily one by one as the iterator is advanced, unlike `search_many`
+    /// the results are never collected, for streaming a large log through the searcher
+    pub fn search_batch_iter<'a, I>(
+        &'a self,
+        ips: I,
+    ) -> impl Iterator<Item = Result<IpInfo, Ip2RegionError>> + 'a
+    where
+        I: IntoIterator<Item = u32> + 'a,
+    {
+        ips.into_iter().map(move |ip| self.search(ip))
+    }
+
+    /// the number of the ips of every region keyed by the raw `country|region|province|city|isp`
+    /// string, for the top regions of a request log, the regions are not split into `IpInfo`,
+    /// the first failed search is returned
+    pub fn count_unique_regions<I>(&self, ips: I) -> Result<HashMap<String, u64>, Ip2RegionError>
+    where
+        I: IntoIterator<Item = u32>,
+    {
+        let mut counts = HashMap::new();
+        for ip in ips {
+            *counts
+                .entry(self.search_result(ip)?.into_inner())
+                .or_insert(0) += 1;
+        }
+        Ok(counts)
+    }
+
+    /// the whole xdb file buffer, only available for `CachePolicy::Full`
+    pub fn get_full_cache(&self) -> Option<CachedBytes> {
+        match &self.xdb.load().cache {
+            Cache::Full(buffer) => Some(CachedBytes {
+                range: 0..buffer.len(),
+                buffer: Arc::clone(buffer),
+            }),
+            Cache::VecIndex { .. } | Cache::Segmented { .. } | Cache::Never(_) => None,
+        }
+    }
+
+    /// recompute the crc32 stamped by `build_verified_xdb` over the xdb file and compare it to
+    /// the stored one, the whole file is read again unless it is cached by `CachePolicy::Full`,
+    /// an xdb file without a checksum is reported as `Ip2RegionError::InvalidDatabase`
+    pub fn verify_integrity(&self) -> Result<(), Ip2RegionError> {
+        let xdb = self.xdb.load();
+        let expected = stored_checksum(&xdb.header)?;
+        let actual = xdb.with_reader(|reader| {
+            reader.seek(SeekFrom::Start(HEADER_INFO_LENGTH as u64))?;
+            let mut crc = Crc32::new();
+            let mut buffer = vec![0; 64 * 1024];
+            loop {
+                match reader.read(&mut buffer) {
+                    Ok(0) => break,
+                    Ok(n) => crc = crc.update(&buffer[..n]),
+                    Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            Ok(crc.finish())
+        })?;
+        check_checksum(expected, actual)
+    }
+
+    /// the vector index block, not available for `CachePolicy::Never`
+    pub fn get_vector_index_cache(&self) -> Option<CachedBytes> {
+        let xdb = self.xdb.load();
+        match &xdb.cache {
+            Cache::Full(buffer) => Some(CachedBytes {
+                range: HEADER_INFO_LENGTH..(HEADER_INFO_LENGTH + xdb.vector_index_length),
+                buffer: Arc::clone(buffer),
+            }),
+            Cache::VecIndex { vector_index, .. } | Cache::Segmented { vector_index, .. } => {
+                Some(CachedBytes {
+                    range: 0..vector_index.len(),
+                    buffer: Arc::clone(vector_index),
+                })
+            }
+            Cache::Never(_) => None,
+        }
+    }
+
+    /// the bytes of the xdb file cached in memory, the whole file for `CachePolicy::Full`
+    /// (11070083 bytes for the data/ip2region.xdb in this repository), the vector index for
+    /// `CachePolicy::VecIndex`, the vector index and the bytes after it for
+    /// `CachePolicy::Segmented` and 0 for `CachePolicy::Never`, mapped pages are counted
+    /// as a whole though the os may not have loaded them yet
+    pub fn total_size_bytes(&self) -> usize {
+        let xdb = self.xdb.load();
+        xdb.cached_parts().iter().map(|part| part.len()).sum()
+    }
+
+    /// the bytes of the vector index block whatever the cache policy is, it is fixed by the
+    /// index policy in the header, 512 KiB for the only index policy in use
+    pub fn vector_index_size_bytes(&self) -> usize {
+        self.xdb.load().vector_index_length
+    }
+
+    /// touch one byte of every page of the cached buffer so the os has it in physical memory
+    /// before the first search, mostly for `CachePolicy::Mmap` whose pages are loaded lazily.
+    /// the touched bytes are xored into the returned checksum to keep the reads from being
+    /// optimized out, nothing is cached for `CachePolicy::Never` and it returns 0
+    pub fn preload(&self) -> u64 {
+        self.xdb
+            .load()
+            .cached_parts()
+            .iter()
+            .flat_map(|part| part.iter().step_by(PAGE_SIZE))
+            .fold(0, |checksum, &byte| checksum ^ u64::from(byte))
+    }
+
+    /// touch the cached buffer like `preload` a page of 4 KiB at a time, calling `progress`
+    /// with `(bytes_read, total_bytes)` after every page for a startup progress bar,
+    /// `progress` is never called for `CachePolicy::Never` which caches nothing
+    pub fn warm_cache<F: Fn(usize, usize)>(&self, progress: F) {
+        let xdb = self.xdb.load();
+        let parts = xdb.cached_parts();
+        let total_bytes = parts.iter().map(|part| part.len()).sum();
+        let mut bytes_read = 0;
+        for page in parts.iter().flat_map(|part| part.chunks(PAGE_SIZE)) {
+            std::hint::black_box(page[0]);
+            bytes_read += page.len();
+            progress(bytes_read, total_bytes);
+        }
+    }
+
+    /// `warm_cache` without reporting the progress
+    pub fn warm_cache_silent(&self) {
+        self.warm_cache(|_, _| {});
+    }
+}
+
+impl Xdb {
+    pub(crate) fn ip_version(&self) -> IpVersion {
+        self.ip_version
+    }
+
+    /// the buffers cached in memory, in the order of the xdb file, the unused ones are empty
+    fn cached_parts(&self) -> [&[u8]; 2] {
+        match &self.cache {
+            Cache::Full(buffer) => [buffer, &[]],
+            Cache::VecIndex { vector_index, .. } => [vector_index, &[]],
+            Cache::Segmented {
+                vector_index,
+                segments,
+                ..
+            } => [vector_index, segments],
+            Cache::Never(_) => [&[], &[]],
+        }
+    }
+
+    /// the pointers to the first and the last segment index entries
+    pub(crate) fn index_ptrs(&self) -> (usize, usize) {
+        index_ptrs(&self.header)
+    }
+
+    /// the number of segment index entries between the first and the last entry pointers
+    pub(crate) fn segment_count(&self) -> usize {
+        let (start_index_ptr, end_index_ptr) = self.index_ptrs();
+        let segment_index_size = match self.ip_version {
+            IpVersion::V4 => <u32 as SegmentIp>::SEGMENT_INDEX_SIZE,
+            IpVersion::V6 => IPV6_SEGMENT_INDEX_SIZE,
+        };
+        (end_index_ptr - start_index_ptr) / segment_index_size + 1
+    }
+
+    /// the xdb filepath opened on every search of `CachePolicy::Never`
+    pub(crate) fn never_path(&self) -> Option<&Path> {
+        match &self.cache {
+            Cache::Never(Source::Path(xdb_filepath)) => Some(xdb_filepath),
+            _ => None,
+        }
+    }
+
+    /// run `f` with a reader over the whole xdb file, the cached buffer is read in place
+    pub(crate) fn with_reader<T, F>(&self, f: F) -> Result<T, Ip2RegionError>
+    where
+        F: FnOnce(&mut dyn ReadSeek) -> Result<T, Ip2RegionError>,
+    {
+        match &self.cache {
+            Cache::Full(buffer) => f(&mut io::Cursor::new(&buffer[..])),
+            Cache::VecIndex { reader, .. }
+            | Cache::Segmented { reader, .. }
+            | Cache::Never(Source::Reader(reader)) => f(&mut **lock_reader(reader)),
+            Cache::Never(Source::Path(xdb_filepath)) => f(&mut open_xdb_file(xdb_filepath)?),
+        }
+    }
+
+    fn open(xdb_filepath: &Path, cache_policy: CachePolicy) -> Result<Self, Ip2RegionError> {
+        tracing::debug!(
+            "load xdb searcher file at {} with {} cache policy",
+            xdb_filepath.display(),
+            cache_policy
+        );
+        let f = open_xdb_file(xdb_filepath)?;
+        match cache_policy {
+            CachePolicy::Never => {
+                let (_, ip_version, header) = read_header(f)?;
+                Ok(Self {
+                    cache: Cache::Never(Source::Path(xdb_filepath.to_path_buf())),
+                    ip_version,
+                    vector_index_length: vector_index_length(&header)?,
+                    header,
+                })
+            }
+            #[cfg(feature = "memmap2")]
+            CachePolicy::Mmap => Self::from_mmap(xdb_filepath),
+            CachePolicy::VecIndex | CachePolicy::Segmented(_) | CachePolicy::Full => {
+                Self::from_reader(f, cache_policy)
+            }
+        }
+    }
+
+    #[cfg(feature = "memmap2")]
+    fn from_mmap(xdb_filepath: &Path) -> Result<Self, Ip2RegionError> {
+        let f = open_xdb_file(xdb_filepath)?;
+        // SAFETY: the mapping is read only, the caller is told not to modify the file in place
+        let mmap = unsafe { memmap2::Mmap::map(&f)? };
+        let ip_version = check_header(&mmap, mmap.len())?;
+        Ok(Self {
+            header: copy_header(&mmap),
+            vector_index_length: vector_index_length(&mmap)?,
+            cache: Cache::Full(Arc::new(Buffer::Mmap(mmap))),
+            ip_version,
+        })
+    }
+
+    fn from_reader<R>(mut reader: R, cache_policy: CachePolicy) -> Result<Self, Ip2RegionError>
+    where
+        R: Read + Seek + Send + 'static,
+    {
+        reader.rewind()?;
+        if cache_policy == CachePolicy::Full {
+            let mut buffer = Vec::new();
+            reader.read_to_end(&mut buffer)?;
+            return Self::from_bytes(buffer);
+        }
+
+        let (mut reader, ip_version, header) = read_header(reader)?;
+        let vector_index_length = vector_index_length(&header)?;
+        let cache = match cache_policy {
+            CachePolicy::VecIndex => {
+                let mut vector_index = vec![0; vector_index_length];
+                reader.read_exact(&mut vector_index)?;
+                Cache::VecIndex {
+                    vector_index: Arc::new(Buffer::Heap(vector_index)),
+                    reader: Mutex::new(Box::new(reader)),
+                }
+            }
+            CachePolicy::Segmented(bytes) => {
+                let mut vector_index = vec![0; vector_index_length];
+                reader.read_exact(&mut vector_index)?;
+                let mut segments = Vec::new();
+                (&mut reader)
+                    .take(bytes as u64)
+                    .read_to_end(&mut segments)?;
+                let segments = Arc::new(Buffer::Heap(segments));
+                Cache::Segmented {
+                    vector_index: Arc::new(Buffer::Heap(vector_index)),
+                    segments: Arc::clone(&segments),
+                    bytes,
+                    reader: Mutex::new(Box::new(SegmentedReader {
+                        segments_start: (HEADER_INFO_LENGTH + vector_index_length) as u64,
+                        segments,
+                        inner: reader,
+                        pos: 0,
+                    })),
+                }
+            }
+            // only a file can be mapped
+            #[cfg(feature = "memmap2")]
+            CachePolicy::Mmap => return Err(Ip2RegionError::UnsupportedPolicy(cache_policy)),
+            CachePolicy::Never | CachePolicy::Full => {
+                Cache::Never(Source::Reader(Mutex::new(Box::new(reader))))
+            }
+        };
+        Ok(Self {
+            cache,
+            ip_version,
+            header,
+            vector_index_length,
+        })
+    }
+
+    fn from_bytes(buffer: Vec<u8>) -> Result<Self, Ip2RegionError> {
+        let ip_version = check_header(&buffer, buffer.len())?;
+        Ok(Self {
+            header: copy_header(&buffer),
+            vector_index_length: vector_index_length(&buffer)?,
+            cache: Cache::Full(Arc::new(Buffer::Heap(buffer))),
+            ip_version,
+        })
+    }
+
+    fn search_region<I: SegmentIp>(&self, ip: I) -> Result<String, Ip2RegionError> {
+        if I::VERSION != self.ip_version {
+            return Err(Ip2RegionError::IpVersionMismatch);
+        }
+        match &self.cache {
+            Cache::Full(buffer) => Ok(String::from_utf8(
+                buffer[search_in_buffer(buffer, self.vector_index_length, ip)?].to_vec(),
+            )?),
+            Cache::VecIndex {
+                vector_index,
+                reader,
+            }
+            | Cache::Segmented {
+                vector_index,
+                reader,
+                ..
+            } => search_in_reader(&mut **lock_reader(reader), Some(vector_index), ip),
+            Cache::Never(Source::Path(xdb_filepath)) => {
+                search_in_reader(&mut open_xdb_file(xdb_filepath)?, None, ip)
+            }
+            Cache::Never(Source::Reader(reader)) => {
+                search_in_reader(&mut **lock_reader(reader), None, ip)
+            }
+        }
+    }
+}
+
+/// read and check the header, the reader is left at the start of the vector index
+fn read_header<R: Read + Seek>(
+    mut reader: R,
+) -> Result<(R, IpVersion, [u8; HEADER_INFO_LENGTH]), Ip2RegionError> {
+    let xdb_length = reader.seek(SeekFrom::End(0))? as usize;
+    reader.rewind()?;
+    let mut header = [0; HEADER_INFO_LENGTH];
+    reader.read_exact(&mut header).map_err(|_| too_short())?;
+    let ip_version = check_header(&header, xdb_length)?;
+    Ok((reader, ip_version, header))
+}
+
+/// the header of a buffer already checked by `check_header`
+fn copy_header(buffer: &[u8]) -> [u8; HEADER_INFO_LENGTH] {
+    let mut header = [0; HEADER_INFO_LENGTH];
+    header.copy_from_slice(&buffer[..HEADER_INFO_LENGTH]);
+    header
+}
+
+/// the reader is seeked before every read, so it is still usable after a panic
+fn lock_reader(reader: &Mutex<Box<dyn ReadSeek>>) -> MutexGuard<'_, Box<dyn ReadSeek>> {
+    reader.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+pub(crate) fn open_xdb_file(xdb_filepath: &Path) -> Result<File, Ip2RegionError> {
+    File::open(xdb_filepath).map_err(|e| open_error(xdb_filepath, e))
+}
+
+pub(crate) fn open_error(xdb_filepath: &Path, e: io::Error) -> Ip2RegionError {
+    match e.kind() {
+        ErrorKind::NotFound => Ip2RegionError::FileNotFound(xdb_filepath.to_path_buf()),
+        _ => e.into(),
+    }
+}
+
+/// same as `search_in_buffer`, but the segment index and region data are read from `reader`,
+/// the vector index entry is also read from `reader` if `vector_index` is not cached
+fn search_in_reader<R: Read + Seek + ?Sized, I: SegmentIp>(
+    reader: &mut R,
+    vector_index: Option<&[u8]>,
+    ip: I,
+) -> Result<String, Ip2RegionError> {
+    let (start_ptr, end_ptr) = match vector_index {
+        Some(vector_index) => get_start_end_ptr(vector_index, ip),
+        None => {
+            let mut vector_index_value = [0; VECTOR_INDEX_SIZE];
+            let offset = HEADER_INFO_LENGTH + ip.vector_index_offset();
+            reader.seek(SeekFrom::Start(offset as u64))?;
+            reader.read_exact(&mut vector_index_value)?;
+            (
+                ptr_at(&vector_index_value, 0),
+                ptr_at(&vector_index_value, 4),
+            )
+        }
+    };
     let mut left: usize = 0;
-    let mut right: usize = (end_ptr - start_ptr) / SEGMENT_INDEX_SIZE;
+    let mut right: usize = last_segment::<I>(start_ptr, end_ptr)?;
+    let mut buffer = [0; IPV6_SEGMENT_INDEX_SIZE];
+    let buffer_ip_value = &mut buffer[..I::SEGMENT_INDEX_SIZE];
 
+    let span = BinarySearchSpan::enter(start_ptr, end_ptr);
+    let mut iterations = 0;
+    let mut found = None;
     while left <= right {
+        iterations += 1;
         let mid = (left + right) >> 1;
-        let offset = start_ptr + mid * SEGMENT_INDEX_SIZE;
-        let buffer_ip_value = &get_full_cache()[offset..offset + SEGMENT_INDEX_SIZE];
-        let start_ip = get_block_by_size(buffer_ip_value, 0, 4);
-        if ip < (start_ip as u32) {
-            right = mid - 1;
-        } else if ip > (get_block_by_size(buffer_ip_value, 4, 4) as u32) {
+        let offset = start_ptr + mid * I::SEGMENT_INDEX_SIZE;
+        reader.seek(SeekFrom::Start(offset as u64))?;
+        reader.read_exact(buffer_ip_value)?;
+        let start_ip = I::from_segment_bytes(buffer_ip_value);
+        if ip < start_ip {
+            // the ip is below the first segment of the block
+            right = match mid.checked_sub(1) {
+                Some(right) => right,
+                None => break,
+            };
+        } else if ip > I::from_segment_bytes(&buffer_ip_value[I::BYTES..]) {
             left = mid + 1;
         } else {
-            let data_length = get_block_by_size(buffer_ip_value, 8, 2);
-            let data_offset = get_block_by_size(buffer_ip_value, 10, 4);
-            let result = String::from_utf8(
-                get_full_cache()[data_offset..(data_offset + data_length)].to_vec(),
-            );
-            return Ok(result?);
+            let data_length = usize::from(u16_at(buffer_ip_value, I::BYTES * 2));
+            let data_offset = ptr_at(buffer_ip_value, I::BYTES * 2 + 2);
+            found = Some((offset, data_offset, data_length));
+            break;
         }
     }
-    Err("not matched".into())
+    span.exit(iterations, found.map(|(offset, _, _)| offset));
+
+    let (_, data_offset, data_length) = found.ok_or(Ip2RegionError::NotMatched)?;
+    let mut data = vec![0; data_length];
+    reader.seek(SeekFrom::Start(data_offset as u64))?;
+    reader.read_exact(&mut data)?;
+    Ok(String::from_utf8(data)?)
 }
 
-/// it will check ../data/ip2region.xdb, ../../data/ip2region.xdb, ../../../data/ip2region.xdb
-fn default_detect_xdb_file() -> Result<String, Box<dyn Error>> {
+/// search by the searcher initialized in `searcher_init`, it is initialized by
+/// `searcher_init(None)` on the first call and its error is returned if it fails
+pub fn search_by_ip<T>(ip: T) -> Result<IpInfo, Ip2RegionError>
+where
+    T: ToUIntIP,
+{
+    global_searcher()?.search(ip)
+}
+
+/// search a batch of ips by the searcher initialized in `searcher_init`, the outer error is
+/// the one of initializing it like `search_by_ip`
+pub fn search_many(ips: &[u32]) -> Result<Vec<Result<IpInfo, Ip2RegionError>>, Ip2RegionError> {
+    Ok(global_searcher()?.search_many(ips))
+}
+
+/// search an ipv6 address by the searcher initialized in `searcher_init`
+pub fn search_by_ipv6<T>(ip: T) -> Result<IpInfo, Ip2RegionError>
+where
+    T: ToUInt128IP,
+{
+    global_searcher()?.search_by_ipv6(ip)
+}
+
+/// the `data/ip2region.xdb` of the repository baked in at compile time, relative to the
+/// `CARGO_MANIFEST_DIR` of this crate so it does not depend on the working directory
+macro_rules! compile_time_default_path {
+    () => {
+        concat!(env!("CARGO_MANIFEST_DIR"), "/../../../data/ip2region.xdb")
+    };
+}
+
+/// it will check the `compile_time_default_path!` first, then fall back to
+/// ../data/ip2region.xdb, ../../data/ip2region.xdb, ../../../data/ip2region.xdb
+pub(crate) fn default_detect_xdb_file() -> Result<String, Ip2RegionError> {
+    if Path::new(compile_time_default_path!()).exists() {
+        return Ok(compile_time_default_path!().to_owned());
+    }
     let prefix = "../".to_owned();
     for recurse in 1..4 {
         let filepath = prefix.repeat(recurse) + "data/ip2region.xdb";
@@ -64,49 +1063,76 @@ fn default_detect_xdb_file() -> Result<String, Box<dyn Error>> {
             return Ok(filepath);
         }
     }
-    Err("default filepath not find the xdb file, so you must set xdb_filepath".into())
+    Err(Ip2RegionError::FileNotFound(PathBuf::from(
+        "data/ip2region.xdb",
+    )))
 }
 
-#[inline]
-pub fn get_block_by_size(bytes: &[u8], offset: usize, length: usize) -> usize {
-    let mut result: usize = 0;
-    for (index, value) in bytes[offset..offset + length].iter().enumerate() {
-        result += usize::from(*value) << (index << 3);
+/// a `CachePolicy::Full` searcher of the `XDB_FILEPATH` environment variable or else
+/// `default_detect_xdb_file`, for the dependency injection building the services by `Default`,
+/// with the `embed` feature it is the embedded xdb file instead
+///
+/// # Panics
+///
+/// if no xdb file is found or it can not be loaded
+#[cfg(not(feature = "embed"))]
+impl Default for Searcher {
+    fn default() -> Self {
+        let xdb_filepath = std::env::var("XDB_FILEPATH")
+            .ok()
+            .or_else(|| default_detect_xdb_file().ok())
+            .expect("no xdb file found, set XDB_FILEPATH to the xdb file");
+        Searcher::new(&xdb_filepath)
+            .unwrap_or_else(|e| panic!("load the xdb file {xdb_filepath} error: {e}"))
     }
-    result
 }
 
-pub fn searcher_init(xdb_filepath: Option<String>) {
-    let xdb_filepath = xdb_filepath.unwrap_or_else(|| default_detect_xdb_file().unwrap());
-    std::env::set_var(XDB_FILEPATH_ENV, xdb_filepath);
-    CACHE.get_or_init(load_file);
+/// init the global searcher used by `search_by_ip`, only the first successful call loads the
+/// xdb file, the later ones return `Ok` without loading anything.
+///
+/// without a filepath it falls back to the `XDB_FILEPATH` environment variable and then
+/// `default_detect_xdb_file`, the cache policy can be set by `XDB_CACHE_POLICY` with the
+/// names of `CachePolicy` display, `full` by default, a missing or invalid xdb file and an
+/// invalid `XDB_CACHE_POLICY` are returned as the error and the searcher stays uninitialized
+pub fn searcher_init(xdb_filepath: Option<String>) -> Result<(), Ip2RegionError> {
+    init_global_searcher(xdb_filepath).map(|_| ())
 }
 
-pub fn get_vector_index_cache() -> &'static [u8] {
-    let full_cache: &'static Vec<u8> = get_full_cache();
-    &full_cache[HEADER_INFO_LENGTH..(HEADER_INFO_LENGTH + VECTOR_INDEX_LENGTH)]
+fn init_global_searcher(xdb_filepath: Option<String>) -> Result<&'static Searcher, Ip2RegionError> {
+    let _init = SEARCHER_INIT.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(searcher) = SEARCHER.get() {
+        return Ok(searcher);
+    }
+    let searcher = load_global_searcher(xdb_filepath)?;
+    // only set under the lock, so it is always empty here
+    Ok(SEARCHER.get_or_init(|| searcher))
 }
 
-fn load_file() -> Vec<u8> {
-    let xdb_filepath =
-        std::env::var("XDB_FILEPATH").unwrap_or_else(|_| default_detect_xdb_file().unwrap());
-    tracing::debug!("load xdb searcher file at {} ", xdb_filepath);
-    let mut f = File::open(xdb_filepath).expect("file open error");
-    let mut buffer = Vec::new();
-    f.read_to_end(&mut buffer).expect("load file error");
-    buffer
+fn load_global_searcher(xdb_filepath: Option<String>) -> Result<Searcher, Ip2RegionError> {
+    let xdb_filepath = match xdb_filepath.or_else(|| std::env::var("XDB_FILEPATH").ok()) {
+        Some(xdb_filepath) => xdb_filepath,
+        None => default_detect_xdb_file()?,
+    };
+    let cache_policy = match std::env::var("XDB_CACHE_POLICY") {
+        Ok(policy) => policy.parse()?,
+        Err(_) => CachePolicy::default(),
+    };
+    Searcher::with_cache_policy(xdb_filepath, cache_policy)
 }
 
-pub fn get_full_cache() -> &'static Vec<u8> {
-    CACHE.get_or_init(load_file)
+/// the global searcher, it is initialized by `searcher_init(None)` if it is not yet, and the
+/// error of that is returned to the caller of the global functions
+pub(crate) fn global_searcher() -> Result<&'static Searcher, Ip2RegionError> {
+    match SEARCHER.get() {
+        Some(searcher) => Ok(searcher),
+        None => init_global_searcher(None),
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::fs::File;
-    use std::io::Read;
     use std::net::Ipv4Addr;
-    use std::str::FromStr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use std::thread;
 
     use super::*;
@@ -114,37 +1140,32 @@ mod tests {
     ///test all types find correct
     #[test]
     fn test_multi_type_ip() {
-        searcher_init(None);
+        searcher_init(None).unwrap();
 
         search_by_ip("2.0.0.0").unwrap();
         search_by_ip("32").unwrap();
         search_by_ip(4294408949).unwrap();
         search_by_ip(Ipv4Addr::from_str("1.1.1.1").unwrap()).unwrap();
+        assert_eq!(
+            search_by_ip([1, 0, 1, 0]).unwrap(),
+            search_by_ip("1.0.1.0").unwrap()
+        );
     }
 
     #[test]
     fn test_match_all_ip_correct() {
-        searcher_init(None);
-        let mut file = File::open("../../../data/ip.test.txt").unwrap();
-        let mut contents = String::new();
-        file.read_to_string(&mut contents).unwrap();
-        for line in contents.split("\n") {
-            if !line.contains("|") {
-                continue;
-            }
-            let ip_test_line = line.splitn(3, "|").collect::<Vec<&str>>();
-            let start_ip = Ipv4Addr::from_str(ip_test_line[0]).unwrap();
-            let end_ip = Ipv4Addr::from_str(ip_test_line[1]).unwrap();
-            for value in u32::from(start_ip)..u32::from(end_ip) + 1 {
-                let result = search_by_ip(value).unwrap();
-                assert_eq!(result.as_str(), ip_test_line[2])
-            }
-        }
+        searcher_init(None).unwrap();
+        let xdb_filepath = default_detect_xdb_file().unwrap();
+        let test_filepath = Path::new(&xdb_filepath).with_file_name("ip.test.txt");
+        let report =
+            crate::validate_against_testfile(global_searcher().unwrap(), &test_filepath).unwrap();
+        assert!(report.total > 0);
+        assert_eq!(report.mismatched, []);
     }
 
     #[test]
     fn test_multi_thread_only_load_xdb_once() {
-        searcher_init(None);
+        searcher_init(None).unwrap();
         let handle = thread::spawn(|| {
             let result = search_by_ip("2.2.2.2").unwrap();
             println!("ip search in spawn: {result}");
@@ -154,15 +1175,973 @@ mod tests {
         handle.join().unwrap();
     }
 
+    #[test]
+    fn test_share_searcher_between_threads() {
+        let xdb_filepath = default_detect_xdb_file().unwrap();
+        for cache_policy in [CachePolicy::Never, CachePolicy::VecIndex, CachePolicy::Full] {
+            let searcher =
+                Arc::new(Searcher::with_cache_policy(&xdb_filepath, cache_policy).unwrap());
+            let handles: Vec<_> = (0..32u32)
+                .map(|i| {
+                    let searcher = Arc::clone(&searcher);
+                    thread::spawn(move || {
+                        for ip in (i..u32::MAX).step_by(99_733_331) {
+                            searcher.search(ip).unwrap();
+                        }
+                    })
+                })
+                .collect();
+            for handle in handles {
+                handle.join().unwrap();
+            }
+        }
+    }
+
     #[test]
     fn test_multi_searcher_init() {
         for _ in 0..5 {
             thread::spawn(|| {
-                searcher_init(None);
+                searcher_init(None).unwrap();
             });
         }
-        searcher_init(None);
-        searcher_init(Some(String::from("test")));
+        searcher_init(None).unwrap();
+        searcher_init(Some(String::from("test"))).unwrap();
         search_by_ip(123).unwrap();
+
+        assert!(matches!(
+            load_global_searcher(Some(String::from("test"))),
+            Err(Ip2RegionError::FileNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_multi_searcher_instance() {
+        let xdb_filepath = default_detect_xdb_file().unwrap();
+        let first = Searcher::new(&xdb_filepath).unwrap();
+        let second = Searcher::new(&xdb_filepath).unwrap();
+        assert_eq!(
+            first.search("1.0.1.0").unwrap().raw(),
+            "中国|0|福建省|福州市|电信"
+        );
+        assert_eq!(
+            first.search("1.0.1.0").unwrap(),
+            second.search("1.0.1.0").unwrap()
+        );
+        assert!(matches!(
+            Searcher::new("not_exists.xdb"),
+            Err(Ip2RegionError::FileNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_vec_index_cache_policy() {
+        let xdb_filepath = default_detect_xdb_file().unwrap();
+        let full = Searcher::new(&xdb_filepath).unwrap();
+        let vec_index = Searcher::with_cache_policy(&xdb_filepath, CachePolicy::VecIndex).unwrap();
+        assert_eq!(vec_index.cache_policy(), CachePolicy::VecIndex);
+        assert!(vec_index.get_full_cache().is_none());
+        assert_eq!(
+            &*full.get_vector_index_cache().unwrap(),
+            &*vec_index.get_vector_index_cache().unwrap()
+        );
+        for ip in (0..u32::MAX).step_by(997_331) {
+            assert_eq!(full.search(ip).unwrap(), vec_index.search(ip).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_segmented_cache_policy() {
+        let xdb_filepath = default_detect_xdb_file().unwrap();
+        let full = Searcher::new(&xdb_filepath).unwrap();
+        let rest = full.total_size_bytes() - HEADER_INFO_LENGTH - full.vector_index_size_bytes();
+        for bytes in [0, 4096, 1 << 20, usize::MAX] {
+            let cache_policy = CachePolicy::Segmented(bytes);
+            let segmented = Searcher::with_cache_policy(&xdb_filepath, cache_policy).unwrap();
+            assert_eq!(segmented.cache_policy(), cache_policy);
+            assert_eq!(
+                segmented.total_size_bytes(),
+                full.vector_index_size_bytes() + bytes.min(rest)
+            );
+            assert!(segmented.get_full_cache().is_none());
+            for ip in (0..u32::MAX).step_by(997_331) {
+                assert_eq!(full.search(ip).unwrap(), segmented.search(ip).unwrap());
+            }
+            segmented.reload(&xdb_filepath).unwrap();
+            assert_eq!(segmented.cache_policy(), cache_policy);
+        }
+    }
+
+    #[test]
+    fn test_segmented_reader() {
+        let file: Vec<u8> = (0..=255).collect();
+        let mut reader = SegmentedReader {
+            segments_start: 16,
+            segments: Arc::new(Buffer::Heap(vec![0; 16])),
+            inner: io::Cursor::new(file),
+            pos: 0,
+        };
+        // the bytes 16..32 are served from the zeroed cache to tell them apart
+        let mut buffer = [0xFF; 24];
+        reader.seek(SeekFrom::Start(12)).unwrap();
+        reader.read_exact(&mut buffer).unwrap();
+        assert_eq!(buffer[..4], [12, 13, 14, 15]);
+        assert_eq!(buffer[4..20], [0; 16]);
+        assert_eq!(buffer[20..], [32, 33, 34, 35]);
+        assert_eq!(reader.stream_position().unwrap(), 36);
+        assert_eq!(reader.seek(SeekFrom::Current(-6)).unwrap(), 30);
+        assert_eq!(reader.seek(SeekFrom::End(-1)).unwrap(), 255);
+        assert!(reader.seek(SeekFrom::Current(-256)).is_err());
+    }
+
+    #[test]
+    fn test_never_cache_policy() {
+        let xdb_filepath = default_detect_xdb_file().unwrap();
+        let full = Searcher::new(&xdb_filepath).unwrap();
+        let never = Searcher::with_cache_policy(&xdb_filepath, CachePolicy::Never).unwrap();
+        assert_eq!(never.cache_policy(), CachePolicy::Never);
+        // nothing of the xdb file is kept in memory
+        assert!(never.get_full_cache().is_none());
+        assert!(never.get_vector_index_cache().is_none());
+        assert_eq!(never.total_size_bytes(), 0);
+        for ip in (0..u32::MAX).step_by(997_331) {
+            assert_eq!(full.search(ip).unwrap(), never.search(ip).unwrap());
+        }
+
+        /// counts the bytes read through it
+        struct CountingReader {
+            inner: io::Cursor<Vec<u8>>,
+            bytes_read: Arc<AtomicUsize>,
+        }
+
+        impl Read for CountingReader {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                let length = self.inner.read(buf)?;
+                self.bytes_read.fetch_add(length, Ordering::Relaxed);
+                Ok(length)
+            }
+        }
+
+        impl Seek for CountingReader {
+            fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+                self.inner.seek(pos)
+            }
+        }
+
+        // a search only reads its vector index entry, the segment index entries of the binary
+        // search and the region, nothing else of the xdb file
+        let bytes_read = Arc::new(AtomicUsize::new(0));
+        let reader = CountingReader {
+            inner: io::Cursor::new(std::fs::read(&xdb_filepath).unwrap()),
+            bytes_read: Arc::clone(&bytes_read),
+        };
+        let never = Searcher::from_reader(reader, CachePolicy::Never).unwrap();
+        for ip in (0..u32::MAX).step_by(99_733_111) {
+            bytes_read.store(0, Ordering::Relaxed);
+            let region = never.search(ip).unwrap().raw();
+            let segment_index_bytes =
+                bytes_read.load(Ordering::Relaxed) - VECTOR_INDEX_SIZE - region.len();
+            assert_eq!(segment_index_bytes % crate::SEGMENT_INDEX_SIZE, 0);
+            // a binary search of at most 65536 segments of a vector index entry
+            assert!((1..=17).contains(&(segment_index_bytes / crate::SEGMENT_INDEX_SIZE)));
+        }
+    }
+
+    #[test]
+    fn test_from_bytes() {
+        let xdb_filepath = default_detect_xdb_file().unwrap();
+        let full = Searcher::new(&xdb_filepath).unwrap();
+        let from_bytes = Searcher::from_bytes(std::fs::read(&xdb_filepath).unwrap()).unwrap();
+        assert_eq!(from_bytes.cache_policy(), CachePolicy::Full);
+        for ip in (0..u32::MAX).step_by(997_331) {
+            assert_eq!(full.search(ip).unwrap(), from_bytes.search(ip).unwrap());
+        }
+        assert!(matches!(
+            Searcher::from_bytes(Vec::new()),
+            Err(Ip2RegionError::InvalidDatabase { .. })
+        ));
+    }
+
+    #[test]
+    fn test_search_by_ipv6() {
+        let ipv6 = |ip: &str| u128::from(std::net::Ipv6Addr::from_str(ip).unwrap());
+        let segments = [
+            (0, ipv6("2001:db8::") - 1, "0|0|0|0|0"),
+            (
+                ipv6("2001:db8::"),
+                ipv6("2001:db8::ffff"),
+                "中国|0|广东省|深圳市|电信",
+            ),
+            (ipv6("2001:db8::1:0"), u128::MAX, "美国|0|0|0|0"),
+        ];
+        let buffer = crate::maker::make_xdb(&segments);
+        let xdb_filepath = std::env::temp_dir().join("xdb_test_search_by_ipv6.xdb");
+        std::fs::write(&xdb_filepath, &buffer).unwrap();
+        for searcher in [
+            Searcher::from_bytes(buffer).unwrap(),
+            Searcher::with_cache_policy(&xdb_filepath, CachePolicy::VecIndex).unwrap(),
+            Searcher::with_cache_policy(&xdb_filepath, CachePolicy::Never).unwrap(),
+        ] {
+            assert_eq!(searcher.ip_version(), IpVersion::V6);
+            let info = searcher.search_by_ipv6("2001:db8::1").unwrap();
+            assert_eq!(info.raw(), "中国|0|广东省|深圳市|电信");
+            let ip = std::net::Ipv6Addr::from_str("2001:db8::1:0").unwrap();
+            assert_eq!(searcher.search_by_ipv6(ip).unwrap().country, "美国");
+            assert_eq!(searcher.search_by_ipv6(1u128).unwrap().country, "0");
+            assert_eq!(
+                searcher.search_u128(u128::from(ip)).unwrap(),
+                searcher.search_by_ipv6(ip.octets()).unwrap()
+            );
+            assert_eq!(
+                searcher.search_u128(0x2001_0db8 << 96 | 0xffff).unwrap(),
+                info
+            );
+            assert!(matches!(
+                searcher.search("1.1.1.1"),
+                Err(Ip2RegionError::IpVersionMismatch)
+            ));
+        }
+
+        let searcher = Searcher::new(default_detect_xdb_file().unwrap()).unwrap();
+        assert_eq!(searcher.ip_version(), IpVersion::V4);
+        assert!(matches!(
+            searcher.search_by_ipv6("2001:db8::1"),
+            Err(Ip2RegionError::IpVersionMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_search_raw() {
+        let xdb_filepath = default_detect_xdb_file().unwrap();
+        let searcher = Searcher::new(&xdb_filepath).unwrap();
+        assert_eq!(
+            &*searcher
+                .search_raw(u32::from(Ipv4Addr::new(1, 0, 1, 0)))
+                .unwrap(),
+            "中国|0|福建省|福州市|电信".as_bytes()
+        );
+
+        let searcher = Searcher::with_cache_policy(&xdb_filepath, CachePolicy::VecIndex).unwrap();
+        assert!(matches!(
+            searcher.search_raw(0),
+            Err(Ip2RegionError::UnsupportedPolicy(CachePolicy::VecIndex))
+        ));
+
+        // the bytes are returned as they are, even when they are not utf-8
+        let mut buffer = crate::maker::make_xdb(&[(0u32, u32::MAX, "中国|0|0|0|0")]);
+        buffer[HEADER_INFO_LENGTH + crate::VECTOR_INDEX_LENGTH] = 0xFF;
+        let searcher = Searcher::from_bytes(buffer).unwrap();
+        assert_eq!(searcher.search_raw(0).unwrap()[0], 0xFF);
+        let raw: &[u8] = &searcher.raw_segment_bytes(0).unwrap();
+        assert_eq!(raw, b"\xFF\xB8\xAD\xE5\x9B\xBD|0|0|0|0");
+        assert!(matches!(
+            searcher.search(0u32),
+            Err(Ip2RegionError::InvalidUtf8(_))
+        ));
+    }
+
+    #[test]
+    fn test_search_and_parse() {
+        let xdb_filepath = default_detect_xdb_file().unwrap();
+        let full = Searcher::new(&xdb_filepath).unwrap();
+        let never = Searcher::with_cache_policy(&xdb_filepath, CachePolicy::Never).unwrap();
+        for ip in (0..u32::MAX).step_by(99_733_331) {
+            let info = full.search_and_parse(ip).unwrap();
+            assert_eq!(info, never.search_and_parse(ip).unwrap());
+            assert_eq!(info, full.search(ip).unwrap());
+        }
+
+        let segments = [(0u32, u32::MAX, "中国|0|福建省|福州市")];
+        let searcher = Searcher::from_bytes(crate::maker::make_xdb(&segments)).unwrap();
+        assert!(matches!(
+            searcher.search_and_parse("1.0.1.0"),
+            Err(Ip2RegionError::InvalidDatabase { .. })
+        ));
+        let segments = [(0, u128::MAX, "0|0|0|0|0")];
+        let searcher = Searcher::from_bytes(crate::maker::make_xdb(&segments)).unwrap();
+        assert!(matches!(
+            searcher.search_and_parse("1.0.1.0"),
+            Err(Ip2RegionError::IpVersionMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_country() {
+        let xdb_filepath = default_detect_xdb_file().unwrap();
+        let searcher = Searcher::new(&xdb_filepath).unwrap();
+        assert_eq!(&*searcher.country("1.0.1.0").unwrap(), "中国");
+        assert_eq!(searcher.country("8.8.8.8").unwrap().to_string(), "美国");
+
+        let searcher = Searcher::with_cache_policy(&xdb_filepath, CachePolicy::Never).unwrap();
+        assert!(matches!(
+            searcher.country("1.0.1.0"),
+            Err(Ip2RegionError::UnsupportedPolicy(CachePolicy::Never))
+        ));
+    }
+
+    #[test]
+    fn test_lookup_asn() {
+        let segments = [
+            (0u32, 0x00FF_FFFF, "0|0|0|0|0"),
+            (0x0100_0000, u32::MAX, "中国|0|广东省|深圳市|电信 AS4134"),
+        ];
+        let searcher = Searcher::from_bytes(crate::maker::make_xdb(&segments)).unwrap();
+        assert_eq!(searcher.lookup_asn("1.0.1.0").unwrap(), Some(4134));
+        assert_eq!(searcher.lookup_asn("0.0.0.1").unwrap(), None);
+
+        let searcher = Searcher::new(default_detect_xdb_file().unwrap()).unwrap();
+        assert_eq!(searcher.lookup_asn("1.0.1.0").unwrap(), None);
+    }
+
+    #[test]
+    fn test_is_chinese_mainland() {
+        let searcher = Searcher::new(default_detect_xdb_file().unwrap()).unwrap();
+        for (ip, mainland) in [
+            ("1.2.2.0", true),
+            ("1.0.1.0", true),
+            ("1.32.192.0", false),
+            ("23.36.128.0", false),
+            ("1.32.208.0", false),
+            ("8.8.8.8", false),
+        ] {
+            assert_eq!(searcher.is_chinese_mainland(ip).unwrap(), mainland, "{ip}");
+        }
+    }
+
+    #[test]
+    fn test_search_many() {
+        let xdb_filepath = default_detect_xdb_file().unwrap();
+        let ips: Vec<u32> = (0..u32::MAX).step_by(997_331).collect();
+        for cache_policy in [CachePolicy::Never, CachePolicy::VecIndex, CachePolicy::Full] {
+            let searcher = Searcher::with_cache_policy(&xdb_filepath, cache_policy).unwrap();
+            let results = searcher.search_many(&ips);
+            assert_eq!(results.len(), ips.len());
+            for (ip, result) in ips.iter().zip(results) {
+                assert_eq!(result.unwrap(), searcher.search(*ip).unwrap());
+            }
+        }
+
+        searcher_init(None).unwrap();
+        assert_eq!(search_many(&[]).unwrap().len(), 0);
+        assert_eq!(search_many(&[1, 2, 3]).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_search_batch_iter() {
+        let searcher = Searcher::new(default_detect_xdb_file().unwrap()).unwrap();
+        let ips = (0..u32::MAX).step_by(997_331);
+        let results: Vec<_> = searcher.search_batch_iter(ips.clone()).collect();
+        assert_eq!(results.len(), ips.clone().count());
+        for (ip, result) in ips.zip(results) {
+            assert_eq!(result.unwrap(), searcher.search(ip).unwrap());
+        }
+
+        // only the taken ips are searched
+        let searched = std::cell::Cell::new(0);
+        let ips = (0..).inspect(|_| searched.set(searched.get() + 1));
+        assert_eq!(searcher.search_batch_iter(ips).take(3).count(), 3);
+        assert_eq!(searched.get(), 3);
+    }
+
+    #[test]
+    fn test_count_unique_regions() {
+        let segments = [
+            (0u32, 0x0100_00FF, "澳大利亚|0|0|0|0"),
+            (0x0100_0100, 0x0100_03FF, "中国|0|福建省|福州市|电信"),
+            (0x0100_0400, 0x0100_FFFF, "澳大利亚|0|0|0|0"),
+        ];
+        let searcher = Searcher::from_bytes(crate::maker::make_xdb(&segments)).unwrap();
+        let counts = searcher
+            .count_unique_regions([0x0100_0000, 0x0100_0100, 0x0100_0200, 0x0100_0400])
+            .unwrap();
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts["澳大利亚|0|0|0|0"], 2);
+        assert_eq!(counts["中国|0|福建省|福州市|电信"], 2);
+        assert!(searcher.count_unique_regions([]).unwrap().is_empty());
+        assert!(matches!(
+            searcher.count_unique_regions([0x0100_0000, 0x0200_0000]),
+            Err(Ip2RegionError::NotMatched)
+        ));
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let xdb_filepath = default_detect_xdb_file().unwrap();
+        let full = Searcher::new(&xdb_filepath).unwrap();
+        let buffer = std::fs::read(&xdb_filepath).unwrap();
+        let ips: Vec<u32> = (0..u32::MAX).step_by(997_331).collect();
+        for cache_policy in [CachePolicy::Never, CachePolicy::VecIndex, CachePolicy::Full] {
+            let reader = std::io::Cursor::new(buffer.clone());
+            let searcher = Searcher::from_reader(reader, cache_policy).unwrap();
+            assert_eq!(searcher.cache_policy(), cache_policy);
+            for ip in &ips {
+                assert_eq!(full.search(*ip).unwrap(), searcher.search(*ip).unwrap());
+            }
+            for (ip, result) in ips.iter().zip(searcher.search_many(&ips)) {
+                assert_eq!(full.search(*ip).unwrap(), result.unwrap());
+            }
+        }
+
+        let reader = std::io::Cursor::new(vec![0; 100]);
+        assert!(matches!(
+            Searcher::from_reader(reader, CachePolicy::Never),
+            Err(Ip2RegionError::InvalidDatabase { .. })
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_search_as_json() {
+        let searcher = Searcher::new(default_detect_xdb_file().unwrap()).unwrap();
+        let json = searcher.search_as_json("1.0.1.0").unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let object = value.as_object().unwrap();
+        assert_eq!(
+            object.keys().collect::<Vec<_>>(),
+            ["city", "country", "isp", "province", "region"]
+        );
+        assert_eq!(
+            json,
+            r#"{"country":"中国","region":"0","province":"福建省","city":"福州市","isp":"电信"}"#
+        );
+        assert!(matches!(
+            searcher.search_as_json("1.0.1"),
+            Err(Ip2RegionError::InvalidIp(_))
+        ));
+    }
+
+    #[test]
+    fn test_search_as_map() {
+        let searcher = Searcher::new(default_detect_xdb_file().unwrap()).unwrap();
+        let map = searcher.search_as_map("1.0.1.0").unwrap();
+        let mut keys: Vec<_> = map.keys().map(String::as_str).collect();
+        keys.sort_unstable();
+        assert_eq!(keys, ["city", "country", "isp", "province", "region"]);
+        assert_eq!(map["country"], "中国");
+        assert_eq!(map["province"], "福建省");
+        assert_eq!(map["isp"], "电信");
+    }
+
+    #[test]
+    fn test_search_opt() {
+        let segments = [(0x0100_0000u32, 0x0100_03FF, "中国|0|福建省|福州市|电信")];
+        let searcher = Searcher::from_bytes(crate::maker::make_xdb(&segments)).unwrap();
+        assert_eq!(
+            searcher.search_opt("1.0.1.0").unwrap().unwrap(),
+            "中国|0|福建省|福州市|电信"
+        );
+        assert_eq!(searcher.search_opt("1.0.4.0").unwrap(), None);
+        assert_eq!(searcher.search_opt("2.0.0.0").unwrap(), None);
+        assert!(matches!(
+            searcher.search_opt("1.0.1"),
+            Err(Ip2RegionError::InvalidIp(_))
+        ));
+    }
+
+    #[test]
+    fn test_below_first_segment() {
+        let buffer = crate::maker::make_xdb(&[(100u32, 200, "中国|0|0|0|0")]);
+        let xdb_filepath = std::env::temp_dir().join("xdb_test_below_first_segment.xdb");
+        std::fs::write(&xdb_filepath, &buffer).unwrap();
+        for searcher in [
+            Searcher::from_bytes(buffer).unwrap(),
+            Searcher::with_cache_policy(&xdb_filepath, CachePolicy::VecIndex).unwrap(),
+            Searcher::with_cache_policy(&xdb_filepath, CachePolicy::Never).unwrap(),
+        ] {
+            assert!(matches!(
+                searcher.search(50),
+                Err(Ip2RegionError::NotMatched)
+            ));
+            assert_eq!(searcher.search(100).unwrap().country, "中国");
+        }
+    }
+
+    /// the `(start ip, end ip, region)` segments of an ipv4 xdb file
+    type Segments = Vec<(u32, u32, String)>;
+
+    /// the searcher and the segments of the xdb file of the repository, loaded once for the
+    /// property tests
+    fn repository_segments() -> &'static (Searcher, Segments) {
+        static SEGMENTS: OnceLock<(Searcher, Segments)> = OnceLock::new();
+        SEGMENTS.get_or_init(|| {
+            let searcher = Searcher::new(default_detect_xdb_file().unwrap()).unwrap();
+            let segments = searcher
+                .iter_segments()
+                .map(|segment| {
+                    let segment = segment.unwrap();
+                    (segment.start_ip, segment.end_ip, segment.info.raw())
+                })
+                .collect();
+            (searcher, segments)
+        })
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_search_in_segment(ip: u32, other: u32) {
+            let (searcher, segments) = repository_segments();
+            match searcher.search(ip) {
+                Ok(info) => {
+                    let (start_ip, end_ip, region) =
+                        &segments[segments.partition_point(|&(_, end_ip, _)| end_ip < ip)];
+                    proptest::prop_assert!(*start_ip <= ip && ip <= *end_ip);
+                    proptest::prop_assert_eq!(&info.raw(), region);
+
+                    // the other ips and the bounds of the same segment
+                    let length = u64::from(end_ip - start_ip) + 1;
+                    let other = start_ip + (u64::from(other) % length) as u32;
+                    for ip in [other, *start_ip, *end_ip] {
+                        proptest::prop_assert_eq!(&searcher.search(ip).unwrap(), &info);
+                    }
+                }
+                Err(e) => proptest::prop_assert!(matches!(e, Ip2RegionError::NotMatched)),
+            }
+        }
+    }
+
+    proptest::proptest! {
+        #![proptest_config(proptest::prelude::ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn test_search_made_segments(
+            boundaries in proptest::collection::btree_set(1u32.., 0..32),
+            ips in proptest::collection::vec(proptest::num::u32::ANY, 1..16),
+        ) {
+            // the segments covering all the ips like the xdb files of the maker
+            let starts: Vec<u32> = std::iter::once(0).chain(boundaries).collect();
+            let regions: Vec<String> = (0..starts.len()).map(|i| format!("{i}|0|0|0|0")).collect();
+            let segments: Vec<(u32, u32, &str)> = starts
+                .iter()
+                .enumerate()
+                .map(|(i, &start_ip)| {
+                    let end_ip = starts.get(i + 1).map_or(u32::MAX, |next| next - 1);
+                    (start_ip, end_ip, regions[i].as_str())
+                })
+                .collect();
+            let searcher = Searcher::from_bytes(crate::maker::make_xdb(&segments)).unwrap();
+            for ip in ips {
+                let index = starts.partition_point(|&start_ip| start_ip <= ip) - 1;
+                let (start_ip, end_ip, region) = segments[index];
+                for ip in [ip, start_ip, end_ip] {
+                    proptest::prop_assert_eq!(searcher.search(ip).unwrap().raw(), region);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_core_search() {
+        let buffer = std::fs::read(default_detect_xdb_file().unwrap()).unwrap();
+        let searcher = Searcher::from_bytes(buffer.clone()).unwrap();
+        for ip in (0..u32::MAX).step_by(997_331) {
+            assert_eq!(
+                crate::core_search(&buffer, ip).unwrap(),
+                &*searcher.search_raw(ip).unwrap()
+            );
+        }
+        assert!(matches!(
+            crate::core_search(&buffer[..100], 0),
+            Err(Ip2RegionError::InvalidDatabase { .. })
+        ));
+        let buffer = crate::maker::make_xdb(&[(0, u128::MAX, "0|0|0|0|0")]);
+        assert!(matches!(
+            crate::core_search(&buffer, 0),
+            Err(Ip2RegionError::IpVersionMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_validate_header() {
+        let buffer = crate::maker::make_xdb(&[(0u32, u32::MAX, "0|0|0|0|0")]);
+        validate_header(&buffer).unwrap();
+        validate_header(&buffer[..HEADER_INFO_LENGTH]).unwrap();
+
+        let invalid = |offset: usize, value: u16| {
+            let mut header = buffer[..HEADER_INFO_LENGTH].to_vec();
+            header[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+            match validate_header(&header) {
+                Err(Ip2RegionError::InvalidDatabase { reason }) => reason,
+                result => panic!("unexpected {result:?}"),
+            }
+        };
+        assert!(invalid(0, 1).contains("xdb version 1"));
+        assert!(invalid(2, 2).contains("index policy 2"));
+        assert!(invalid(8, 0).contains("segment index range"));
+        assert!(validate_header(&buffer[..100]).is_err());
+        // the header is fine but the segment index is cut off
+        assert!(matches!(
+            Searcher::from_bytes(buffer[..buffer.len() - 1].to_vec()),
+            Err(Ip2RegionError::InvalidDatabase { .. })
+        ));
+    }
+
+    #[test]
+    fn test_header_fields() {
+        let searcher = Searcher::new(default_detect_xdb_file().unwrap()).unwrap();
+        assert_eq!(searcher.version(), 2);
+        // 2024-02-28 02:32:38 UTC
+        assert_eq!(searcher.build_at(), 1_709_087_558);
+
+        let buffer = crate::maker::make_xdb(&[(0, u128::MAX, "0|0|0|0|0")]);
+        let searcher = Searcher::from_bytes(buffer).unwrap();
+        assert_eq!(searcher.version(), 3);
+    }
+
+    #[test]
+    fn test_reload() {
+        let v4_filepath = default_detect_xdb_file().unwrap();
+        let segments = [(0, u128::MAX, "0|0|0|0|0")];
+        let v6_filepath = std::env::temp_dir().join("xdb_test_reload.xdb");
+        std::fs::write(&v6_filepath, crate::maker::make_xdb(&segments)).unwrap();
+
+        let searcher = Arc::new(Searcher::new(&v4_filepath).unwrap());
+        let raw = searcher.search_raw(123).unwrap();
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let searcher = Arc::clone(&searcher);
+                thread::spawn(move || {
+                    for ip in (0..u32::MAX).step_by(9_973_331) {
+                        searcher.search(ip).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for _ in 0..10 {
+            searcher.reload(&v4_filepath).unwrap();
+        }
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        // the bytes of the old buffer are still valid
+        assert_eq!(&*raw, &*searcher.search_raw(123).unwrap());
+
+        assert!(matches!(
+            searcher.reload("not_exists.xdb"),
+            Err(Ip2RegionError::FileNotFound(_))
+        ));
+        searcher.search(123).unwrap();
+        searcher.reload(&v6_filepath).unwrap();
+        assert_eq!(searcher.ip_version(), IpVersion::V6);
+        assert_eq!(searcher.cache_policy(), CachePolicy::Full);
+        searcher.search_by_ipv6(1u128).unwrap();
+    }
+
+    #[cfg(feature = "memmap2")]
+    #[test]
+    fn test_from_mmap() {
+        let xdb_filepath = default_detect_xdb_file().unwrap();
+        let full = Searcher::new(&xdb_filepath).unwrap();
+        for mmap in [
+            Searcher::from_mmap(Path::new(&xdb_filepath)).unwrap(),
+            Searcher::with_cache_policy(&xdb_filepath, CachePolicy::Mmap).unwrap(),
+        ] {
+            assert_eq!(mmap.cache_policy(), CachePolicy::Mmap);
+            for ip in (0..u32::MAX).step_by(997_331) {
+                assert_eq!(full.search(ip).unwrap(), mmap.search(ip).unwrap());
+            }
+            assert_eq!(
+                &*full.search_raw(123).unwrap(),
+                &*mmap.search_raw(123).unwrap()
+            );
+            mmap.reload(&xdb_filepath).unwrap();
+            assert_eq!(mmap.cache_policy(), CachePolicy::Mmap);
+        }
+        assert!(matches!(
+            Searcher::from_reader(File::open(&xdb_filepath).unwrap(), CachePolicy::Mmap),
+            Err(Ip2RegionError::UnsupportedPolicy(CachePolicy::Mmap))
+        ));
+    }
+
+    #[test]
+    fn test_error_variants() {
+        searcher_init(None).unwrap();
+        assert!(matches!(
+            search_by_ip("1.1.1"),
+            Err(Ip2RegionError::InvalidIp(_))
+        ));
+        let err = Ip2RegionError::from(std::io::Error::from(ErrorKind::PermissionDenied));
+        assert!(matches!(err, Ip2RegionError::Io(_)));
+
+        // the wrapped errors are the sources and left out of the messages, so the reporters
+        // walking the source chain print them once
+        use std::error::Error;
+        assert_eq!(err.to_string(), "io error");
+        let source = err.source().unwrap();
+        assert_eq!(
+            source.downcast_ref::<std::io::Error>().unwrap().kind(),
+            ErrorKind::PermissionDenied
+        );
+        let err = Ip2RegionError::from(String::from_utf8(vec![0xFF]).unwrap_err());
+        assert_eq!(err.to_string(), "invalid utf-8 region data");
+        assert!(err.source().unwrap().is::<std::string::FromUtf8Error>());
+        assert!(Ip2RegionError::NotMatched.source().is_none());
+        assert_eq!(
+            Ip2RegionError::FileNotFound(PathBuf::from("ip2region.xdb")).to_string(),
+            "xdb file not found at ip2region.xdb"
+        );
+    }
+
+    #[test]
+    fn test_verify_integrity() {
+        let xdb_filepath = default_detect_xdb_file().unwrap();
+        let searcher = Searcher::new(&xdb_filepath).unwrap();
+        assert!(matches!(
+            searcher.verify_integrity(),
+            Err(Ip2RegionError::InvalidDatabase { .. })
+        ));
+
+        let mut buffer = std::fs::read(&xdb_filepath).unwrap();
+        crate::build_verified_xdb(&mut buffer).unwrap();
+        let verified_filepath = std::env::temp_dir().join("xdb_test_verify_integrity.xdb");
+        std::fs::write(&verified_filepath, &buffer).unwrap();
+        for cache_policy in [CachePolicy::Full, CachePolicy::VecIndex, CachePolicy::Never] {
+            let searcher = Searcher::with_cache_policy(&verified_filepath, cache_policy).unwrap();
+            searcher.verify_integrity().unwrap();
+            assert_eq!(
+                searcher.search("1.0.1.0").unwrap().raw(),
+                "中国|0|福建省|福州市|电信"
+            );
+        }
+
+        crate::verify_xdb_checksum(&buffer).unwrap();
+        let last = buffer.len() - 1;
+        buffer[last] ^= 1;
+        assert!(crate::verify_xdb_checksum(&buffer).is_err());
+        let searcher = Searcher::from_bytes(buffer).unwrap();
+        assert!(matches!(
+            searcher.verify_integrity(),
+            Err(Ip2RegionError::InvalidDatabase { .. })
+        ));
+        std::fs::remove_file(verified_filepath).unwrap();
+    }
+
+    #[test]
+    fn test_corrupted_vector_index() {
+        let xdb_filepath = default_detect_xdb_file().unwrap();
+        let buffer = std::fs::read(&xdb_filepath).unwrap();
+        // the vector index entry of 1.0.x.x
+        let entry = HEADER_INFO_LENGTH + VECTOR_INDEX_SIZE * 256;
+        let length = buffer.len() as u32;
+        for (start_ptr, end_ptr) in [(length + 14, length + 28), (length - 14, 0)] {
+            let mut buffer = buffer.clone();
+            buffer[entry..entry + 4].copy_from_slice(&start_ptr.to_le_bytes());
+            buffer[entry + 4..entry + 8].copy_from_slice(&end_ptr.to_le_bytes());
+            let searcher = Searcher::from_bytes(buffer.clone()).unwrap();
+            assert!(matches!(
+                searcher.search("1.0.1.0"),
+                Err(Ip2RegionError::InvalidDatabase { .. })
+            ));
+            let searcher =
+                Searcher::from_reader(io::Cursor::new(buffer), CachePolicy::VecIndex).unwrap();
+            assert!(searcher.search("1.0.1.0").is_err());
+        }
+    }
+
+    #[test]
+    fn test_database_info() {
+        let xdb_filepath = default_detect_xdb_file().unwrap();
+        let searcher = Searcher::with_cache_policy(&xdb_filepath, CachePolicy::Never).unwrap();
+        let info = searcher.database_info();
+        assert_eq!(info.version, searcher.version());
+        assert_eq!(info.build_at, searcher.build_at());
+        let (start_index_ptr, end_index_ptr) = searcher.xdb().index_ptrs();
+        assert_eq!(info.start_index_ptr as usize, start_index_ptr);
+        assert_eq!(info.end_index_ptr as usize, end_index_ptr);
+        assert_eq!(info.total_segments as usize, searcher.segment_count());
+        assert_eq!(
+            info.total_segments as usize,
+            searcher.iter_segments().count()
+        );
+    }
+
+    #[test]
+    fn test_search_str() {
+        let searcher = Searcher::new(default_detect_xdb_file().unwrap()).unwrap();
+        assert_eq!(
+            searcher.search_str("1.0.1.0").unwrap(),
+            searcher.search("1.0.1.0").unwrap()
+        );
+        for ip in ["16777472", "1.0.1", "1.0.1.256", ""] {
+            assert!(matches!(
+                searcher.search_str(ip),
+                Err(Ip2RegionError::InvalidIp(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_search_result() {
+        let searcher = Searcher::new(default_detect_xdb_file().unwrap()).unwrap();
+        let result = searcher.search_result("1.0.1.0").unwrap();
+        assert_eq!(&*result, "中国|0|福建省|福州市|电信");
+        assert_eq!(IpInfo::from(result), searcher.search("1.0.1.0").unwrap());
+    }
+
+    #[test]
+    fn test_search_hostname() {
+        let searcher = Searcher::new(default_detect_xdb_file().unwrap()).unwrap();
+        assert_eq!(
+            searcher.search_hostname("localhost").unwrap(),
+            searcher.search("127.0.0.1").unwrap()
+        );
+        assert_eq!(
+            searcher.search_hostname("1.0.1.0").unwrap().raw(),
+            "中国|0|福建省|福州市|电信"
+        );
+        assert!(matches!(
+            searcher.search_hostname("::1"),
+            Err(Ip2RegionError::IpVersionMismatch)
+        ));
+        assert!(matches!(
+            searcher.search_hostname("invalid host name"),
+            Err(Ip2RegionError::Io(_))
+        ));
+    }
+
+    #[cfg(not(feature = "embed"))]
+    #[test]
+    fn test_default() {
+        let searcher = Searcher::default();
+        assert_eq!(searcher.cache_policy(), CachePolicy::Full);
+        assert_eq!(
+            searcher.search("1.0.1.0").unwrap().raw(),
+            "中国|0|福建省|福州市|电信"
+        );
+    }
+
+    #[test]
+    fn test_clone_shares_buffer() {
+        let searcher = Searcher::new(default_detect_xdb_file().unwrap()).unwrap();
+        let cloned = searcher.clone();
+        for ip in ["1.0.1.0", "2.0.0.0", "255.255.255.255"] {
+            assert_eq!(searcher.search(ip).unwrap(), cloned.search(ip).unwrap());
+        }
+        assert!(Arc::ptr_eq(
+            &searcher.get_full_cache().unwrap().buffer,
+            &cloned.get_full_cache().unwrap().buffer
+        ));
+    }
+
+    #[test]
+    fn test_preload() {
+        let xdb_filepath = default_detect_xdb_file().unwrap();
+        let buffer = std::fs::read(&xdb_filepath).unwrap();
+        let checksum = buffer
+            .iter()
+            .step_by(4096)
+            .fold(0, |checksum, &byte| checksum ^ u64::from(byte));
+        assert_eq!(Searcher::new(&xdb_filepath).unwrap().preload(), checksum);
+
+        // the vector index and the segments behind it
+        let segments_start = HEADER_INFO_LENGTH + crate::VECTOR_INDEX_LENGTH;
+        let checksum = [
+            &buffer[HEADER_INFO_LENGTH..segments_start],
+            &buffer[segments_start..segments_start + 10000],
+        ]
+        .iter()
+        .flat_map(|part| part.iter().step_by(4096))
+        .fold(0, |checksum, &byte| checksum ^ u64::from(byte));
+        let segmented =
+            Searcher::with_cache_policy(&xdb_filepath, CachePolicy::Segmented(10000)).unwrap();
+        assert_eq!(segmented.preload(), checksum);
+        let never = Searcher::with_cache_policy(&xdb_filepath, CachePolicy::Never).unwrap();
+        assert_eq!(never.preload(), 0);
+    }
+
+    #[test]
+    fn test_warm_cache() {
+        use std::cell::RefCell;
+
+        let xdb_filepath = default_detect_xdb_file().unwrap();
+        for cache_policy in [
+            CachePolicy::Full,
+            CachePolicy::VecIndex,
+            CachePolicy::Segmented(10000),
+            CachePolicy::Never,
+        ] {
+            let searcher = Searcher::with_cache_policy(&xdb_filepath, cache_policy).unwrap();
+            let total_size_bytes = searcher.total_size_bytes();
+            let calls = RefCell::new(Vec::new());
+            searcher.warm_cache(|bytes_read, total_bytes| {
+                calls.borrow_mut().push((bytes_read, total_bytes));
+            });
+            let calls = calls.into_inner();
+            assert_eq!(calls.len(), (total_size_bytes + PAGE_SIZE - 1) / PAGE_SIZE);
+            assert!(calls.iter().all(|&(_, total)| total == total_size_bytes));
+            assert!(calls
+                .iter()
+                .enumerate()
+                .all(|(i, &(bytes_read, total))| bytes_read == total.min((i + 1) * PAGE_SIZE)));
+            if let Some(&(bytes_read, _)) = calls.last() {
+                assert_eq!(bytes_read, total_size_bytes);
+            }
+            searcher.warm_cache_silent();
+        }
+    }
+
+    #[test]
+    fn test_size_bytes() {
+        let xdb_filepath = default_detect_xdb_file().unwrap();
+        let file_size = std::fs::metadata(&xdb_filepath).unwrap().len() as usize;
+        for (cache_policy, total_size_bytes) in [
+            (CachePolicy::Full, file_size),
+            (CachePolicy::VecIndex, crate::format::VECTOR_INDEX_LENGTH),
+            (CachePolicy::Never, 0),
+        ] {
+            let searcher = Searcher::with_cache_policy(&xdb_filepath, cache_policy).unwrap();
+            assert_eq!(searcher.total_size_bytes(), total_size_bytes);
+            assert_eq!(
+                searcher.vector_index_size_bytes(),
+                crate::format::VECTOR_INDEX_LENGTH
+            );
+            assert_eq!(searcher.vector_index_size_bytes(), 512 * 1024);
+        }
+    }
+
+    #[test]
+    fn test_compile_time_default_path() {
+        let xdb_filepath = default_detect_xdb_file().unwrap();
+        assert_eq!(xdb_filepath, compile_time_default_path!());
+        assert!(Path::new(&xdb_filepath).is_absolute());
+    }
+
+    #[test]
+    fn test_tracing_spans() {
+        #[derive(Clone, Default)]
+        struct Output(Arc<Mutex<Vec<u8>>>);
+
+        impl io::Write for Output {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let output = Output::default();
+        let writer = output.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        let xdb_filepath = default_detect_xdb_file().unwrap();
+        tracing::subscriber::with_default(subscriber, || {
+            for cache_policy in [CachePolicy::Full, CachePolicy::Never] {
+                Searcher::with_cache_policy(&xdb_filepath, cache_policy)
+                    .unwrap()
+                    .search("1.0.1.0")
+                    .unwrap();
+            }
+        });
+
+        let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let closed = output
+            .lines()
+            .filter(|line| line.contains("search{ip=1.0.1.0}:binary_search{iterations="))
+            .count();
+        assert_eq!(closed, 2, "{output}");
     }
 }
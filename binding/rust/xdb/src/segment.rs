@@ -0,0 +1,550 @@
+//! walk the segment index of the ipv4 xdb files in order, without the binary search of every ip
+use std::fs::File;
+use std::io::SeekFrom;
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+
+use arc_swap::Guard;
+use ipnetwork::Ipv4Network;
+
+use crate::format::{ptr_at, u16_at, u32_at, SEGMENT_INDEX_SIZE};
+use crate::searcher::{open_xdb_file, ReadSeek, Xdb};
+use crate::{Ip2RegionError, IpInfo, IpVersion, Searcher, ToUIntIP};
+
+/// an ipv4 segment of the xdb file, all the ips in `start_ip..=end_ip` share the region `info`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Segment {
+    pub start_ip: u32,
+    pub end_ip: u32,
+    pub info: IpInfo,
+}
+
+impl Segment {
+    /// the first ip of the segment
+    pub fn start_addr(&self) -> Ipv4Addr {
+        Ipv4Addr::from(self.start_ip)
+    }
+
+    /// the last ip of the segment, inclusive
+    pub fn end_addr(&self) -> Ipv4Addr {
+        Ipv4Addr::from(self.end_ip)
+    }
+}
+
+/// iterate the segments of a searcher in the order of the segment index, it keeps walking the
+/// xdb file loaded when it was created even if the searcher is reloaded meanwhile
+pub struct SegmentIter {
+    xdb: Arc<Xdb>,
+    /// the xdb file of `CachePolicy::Never`, opened once for the whole iteration
+    file: Option<File>,
+    ptr: usize,
+    end_index_ptr: usize,
+    ip_version_checked: bool,
+}
+
+impl Iterator for SegmentIter {
+    type Item = Result<Segment, Ip2RegionError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.ip_version_checked {
+            self.ip_version_checked = true;
+            if self.xdb.ip_version() != IpVersion::V4 {
+                self.ptr = self.end_index_ptr + 1;
+                return Some(Err(Ip2RegionError::IpVersionMismatch));
+            }
+        }
+        if self.ptr > self.end_index_ptr {
+            return None;
+        }
+        let ptr = self.ptr;
+        self.ptr += SEGMENT_INDEX_SIZE;
+        let read_segment = |reader: &mut dyn ReadSeek| {
+            let entry = read_entry(reader, ptr)?;
+            Ok(Segment {
+                start_ip: entry.start_ip,
+                end_ip: entry.end_ip,
+                info: IpInfo::try_from(read_region(reader, &entry)?)?,
+            })
+        };
+        let segment = match self.xdb.never_path() {
+            Some(xdb_filepath) => {
+                match self
+                    .file
+                    .take()
+                    .map_or_else(|| open_xdb_file(xdb_filepath), Ok)
+                {
+                    Ok(file) => read_segment(self.file.insert(file)),
+                    Err(e) => Err(e),
+                }
+            }
+            None => self.xdb.with_reader(read_segment),
+        };
+        if segment.is_err() {
+            // no more segments after a broken one
+            self.ptr = self.end_index_ptr + 1;
+        }
+        Some(segment)
+    }
+}
+
+/// a raw ipv4 segment index entry
+struct SegmentEntry {
+    start_ip: u32,
+    end_ip: u32,
+    data_length: usize,
+    data_offset: usize,
+}
+
+impl Searcher {
+    /// the number of segment index entries, from the first and the last entry pointers
+    /// in the header
+    pub fn segment_count(&self) -> usize {
+        self.xdb().segment_count()
+    }
+
+    /// iterate all the segments without the binary search, for exporting the whole xdb file,
+    /// only ipv4 xdb files are supported, an ipv6 one yields `IpVersionMismatch` once
+    pub fn iter_segments(&self) -> SegmentIter {
+        let xdb = Guard::into_inner(self.xdb());
+        let (start_index_ptr, end_index_ptr) = xdb.index_ptrs();
+        SegmentIter {
+            xdb,
+            file: None,
+            ptr: start_index_ptr,
+            end_index_ptr,
+            ip_version_checked: false,
+        }
+    }
+
+    /// all the distinct regions in `network`, the adjacent ips of the same region are grouped
+    /// into the fewest cidr blocks, the segment index is walked from the first segment of
+    /// the network instead of searching every ip
+    pub fn search_range(
+        &self,
+        network: Ipv4Network,
+    ) -> Result<Vec<(Ipv4Network, IpInfo)>, Ip2RegionError> {
+        let xdb = self.xdb();
+        if xdb.ip_version() != IpVersion::V4 {
+            return Err(Ip2RegionError::IpVersionMismatch);
+        }
+        let (start_index_ptr, end_index_ptr) = xdb.index_ptrs();
+        let first_ip = u32::from(network.network());
+        let last_ip = u32::from(network.broadcast());
+        xdb.with_reader(|reader| {
+            let left = first_ending_at(reader, start_index_ptr, end_index_ptr, first_ip)?;
+
+            // the (start ip, end ip, region) runs inside the network
+            let mut runs: Vec<(u32, u32, String)> = Vec::new();
+            let mut ptr = start_index_ptr + left * SEGMENT_INDEX_SIZE;
+            while ptr <= end_index_ptr {
+                let entry = read_entry(reader, ptr)?;
+                if entry.start_ip > last_ip {
+                    break;
+                }
+                let start_ip = entry.start_ip.max(first_ip);
+                let end_ip = entry.end_ip.min(last_ip);
+                let region = read_region(reader, &entry)?;
+                match runs.last_mut() {
+                    Some(run) if run.2 == region && u64::from(run.1) + 1 == u64::from(start_ip) => {
+                        run.1 = end_ip;
+                    }
+                    _ => runs.push((start_ip, end_ip, region)),
+                }
+                ptr += SEGMENT_INDEX_SIZE;
+            }
+
+            let mut result = Vec::new();
+            for (start_ip, end_ip, region) in runs {
+                for block in cidr_blocks(start_ip, end_ip) {
+                    result.push((block, IpInfo::try_from(region.clone())?));
+                }
+            }
+            Ok(result)
+        })
+    }
+
+    /// the ip distance to the nearest segment and its region, for the ips in the gaps of the
+    /// xdb files not covering every ip, the distance is 0 when the ip is matched. a gap
+    /// exactly in the middle of two segments takes the lower one, only ipv4 xdb files are
+    /// supported
+    pub fn closest_match(&self, ip: u32) -> Result<(u32, IpInfo), Ip2RegionError> {
+        match self.search(ip) {
+            Err(Ip2RegionError::NotMatched) => {}
+            result => return result.map(|info| (0, info)),
+        }
+        let xdb = self.xdb();
+        let (start_index_ptr, end_index_ptr) = xdb.index_ptrs();
+        xdb.with_reader(|reader| {
+            // the ip is not in the first segment ending after it
+            let count = (end_index_ptr - start_index_ptr) / SEGMENT_INDEX_SIZE + 1;
+            let left = first_ending_at(reader, start_index_ptr, end_index_ptr, ip)?;
+
+            let mut entry_at =
+                |index: usize| read_entry(reader, start_index_ptr + index * SEGMENT_INDEX_SIZE);
+            let below = left.checked_sub(1).map(&mut entry_at).transpose()?;
+            let above = (left < count).then(|| entry_at(left)).transpose()?;
+            let (distance, entry) = match (below, above) {
+                (Some(below), Some(above)) if above.start_ip - ip < ip - below.end_ip => {
+                    (above.start_ip - ip, above)
+                }
+                (Some(below), _) => (ip - below.end_ip, below),
+                (None, Some(above)) => (above.start_ip - ip, above),
+                (None, None) => return Err(Ip2RegionError::NotMatched),
+            };
+            Ok((distance, IpInfo::try_from(read_region(reader, &entry)?)?))
+        })
+    }
+
+    /// the adjacent segments in the order of the segment index where the first one does not
+    /// end before the second one starts, for checking the xdb files of the makers, a well
+    /// formed xdb file has none. only ipv4 xdb files are supported like `iter_segments`
+    pub fn overlapping_segments(&self) -> Result<Vec<(Segment, Segment)>, Ip2RegionError> {
+        let mut overlapping = Vec::new();
+        let mut previous: Option<Segment> = None;
+        for segment in self.iter_segments() {
+            let segment = segment?;
+            if let Some(previous) = previous.take().filter(|p| p.end_ip >= segment.start_ip) {
+                overlapping.push((previous, segment.clone()));
+            }
+            previous = Some(segment);
+        }
+        Ok(overlapping)
+    }
+
+    /// the inclusive `(start_ip, end_ip)` ranges covered by no segment, including the ones
+    /// before the first and after the last segment, which are not matched by `search`.
+    /// the segments are walked in the order of the segment index, so the ranges of an
+    /// unsorted xdb file are not meaningful, check `overlapping_segments` first
+    pub fn gap_segments(&self) -> Result<Vec<(u32, u32)>, Ip2RegionError> {
+        let mut gaps = Vec::new();
+        // the first ip not covered by the segments walked so far
+        let mut next_ip = 0u64;
+        for segment in self.iter_segments() {
+            let segment = segment?;
+            if u64::from(segment.start_ip) > next_ip {
+                gaps.push((next_ip as u32, segment.start_ip - 1));
+            }
+            next_ip = next_ip.max(u64::from(segment.end_ip) + 1);
+        }
+        if next_ip <= u64::from(u32::MAX) {
+            gaps.push((next_ip as u32, u32::MAX));
+        }
+        Ok(gaps)
+    }
+
+    /// the whole segment containing the ip with its boundaries, for telling the block an ip
+    /// belongs to, only ipv4 xdb files are supported
+    pub fn find_segment<T>(&self, ip: T) -> Result<Segment, Ip2RegionError>
+    where
+        T: ToUIntIP,
+    {
+        let ip = ip.to_u32_ip()?;
+        let xdb = self.xdb();
+        if xdb.ip_version() != IpVersion::V4 {
+            return Err(Ip2RegionError::IpVersionMismatch);
+        }
+        let (start_index_ptr, end_index_ptr) = xdb.index_ptrs();
+        xdb.with_reader(|reader| {
+            let index = first_ending_at(reader, start_index_ptr, end_index_ptr, ip)?;
+            let ptr = start_index_ptr + index * SEGMENT_INDEX_SIZE;
+            if ptr > end_index_ptr {
+                return Err(Ip2RegionError::NotMatched);
+            }
+            let entry = read_entry(reader, ptr)?;
+            if entry.start_ip > ip {
+                return Err(Ip2RegionError::NotMatched);
+            }
+            Ok(Segment {
+                start_ip: entry.start_ip,
+                end_ip: entry.end_ip,
+                info: IpInfo::try_from(read_region(reader, &entry)?)?,
+            })
+        })
+    }
+}
+
+/// the index of the first segment ending at or after the ip, the segment count when every
+/// segment ends before it
+fn first_ending_at(
+    reader: &mut dyn ReadSeek,
+    start_index_ptr: usize,
+    end_index_ptr: usize,
+    ip: u32,
+) -> Result<usize, Ip2RegionError> {
+    let mut left = 0;
+    let mut right = (end_index_ptr - start_index_ptr) / SEGMENT_INDEX_SIZE + 1;
+    while left < right {
+        let mid = (left + right) >> 1;
+        let entry = read_entry(reader, start_index_ptr + mid * SEGMENT_INDEX_SIZE)?;
+        if entry.end_ip < ip {
+            left = mid + 1;
+        } else {
+            right = mid;
+        }
+    }
+    Ok(left)
+}
+
+fn read_entry(reader: &mut dyn ReadSeek, ptr: usize) -> Result<SegmentEntry, Ip2RegionError> {
+    let mut buffer = [0; SEGMENT_INDEX_SIZE];
+    reader.seek(SeekFrom::Start(ptr as u64))?;
+    reader.read_exact(&mut buffer)?;
+    Ok(SegmentEntry {
+        start_ip: u32_at(&buffer, 0),
+        end_ip: u32_at(&buffer, 4),
+        data_length: usize::from(u16_at(&buffer, 8)),
+        data_offset: ptr_at(&buffer, 10),
+    })
+}
+
+fn read_region(reader: &mut dyn ReadSeek, entry: &SegmentEntry) -> Result<String, Ip2RegionError> {
+    let mut data = vec![0; entry.data_length];
+    reader.seek(SeekFrom::Start(entry.data_offset as u64))?;
+    reader.read_exact(&mut data)?;
+    Ok(String::from_utf8(data)?)
+}
+
+/// split the inclusive ip range into the fewest aligned cidr blocks
+fn cidr_blocks(start_ip: u32, end_ip: u32) -> Vec<Ipv4Network> {
+    let mut blocks = Vec::new();
+    let mut start = u64::from(start_ip);
+    let end = u64::from(end_ip);
+    while start <= end {
+        let mut size = 1u64 << start.trailing_zeros().min(32);
+        while start + size - 1 > end {
+            size >>= 1;
+        }
+        let prefix = 32 - size.trailing_zeros() as u8;
+        blocks.push(Ipv4Network::new(Ipv4Addr::from(start as u32), prefix).unwrap());
+        start += size;
+    }
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::searcher::default_detect_xdb_file;
+    use crate::CachePolicy;
+
+    #[test]
+    fn test_cidr_blocks() {
+        let blocks = |start: &str, end: &str| {
+            cidr_blocks(
+                Ipv4Addr::from_str(start).unwrap().into(),
+                Ipv4Addr::from_str(end).unwrap().into(),
+            )
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(blocks("0.0.0.0", "255.255.255.255"), ["0.0.0.0/0"]);
+        assert_eq!(blocks("10.0.0.1", "10.0.0.1"), ["10.0.0.1/32"]);
+        assert_eq!(
+            blocks("10.0.0.1", "10.0.0.6"),
+            ["10.0.0.1/32", "10.0.0.2/31", "10.0.0.4/31", "10.0.0.6/32"]
+        );
+    }
+
+    #[test]
+    fn test_iter_segments() {
+        let xdb_filepath = default_detect_xdb_file().unwrap();
+        let searcher = Searcher::new(&xdb_filepath).unwrap();
+        let mut next_ip = 0u64;
+        let mut count = 0;
+        for segment in searcher.iter_segments() {
+            let segment = segment.unwrap();
+            // no overlapping and no gap
+            assert_eq!(u64::from(segment.start_ip), next_ip);
+            assert!(segment.start_ip <= segment.end_ip);
+            next_ip = u64::from(segment.end_ip) + 1;
+            count += 1;
+        }
+        assert_eq!(next_ip, 1 << 32);
+        assert_eq!(count, searcher.segment_count());
+
+        for cache_policy in [CachePolicy::Never, CachePolicy::VecIndex] {
+            let searcher = Searcher::with_cache_policy(&xdb_filepath, cache_policy).unwrap();
+            let first: Vec<_> = searcher
+                .iter_segments()
+                .take(100)
+                .map(Result::unwrap)
+                .collect();
+            let full_first: Vec<_> = Searcher::new(&xdb_filepath)
+                .unwrap()
+                .iter_segments()
+                .take(100)
+                .map(Result::unwrap)
+                .collect();
+            assert_eq!(first, full_first);
+        }
+
+        let segments = [(0, u128::MAX, "0|0|0|0|0")];
+        let searcher = Searcher::from_bytes(crate::maker::make_xdb(&segments)).unwrap();
+        assert_eq!(searcher.segment_count(), 256 * 256);
+        let mut segments = searcher.iter_segments();
+        assert!(matches!(
+            segments.next(),
+            Some(Err(Ip2RegionError::IpVersionMismatch))
+        ));
+        assert!(segments.next().is_none());
+    }
+
+    #[test]
+    fn test_closest_match() {
+        let segments = [
+            (0x0A00_0000u32, 0x0A00_00FF, "0|0|0|内网IP|内网IP"),
+            (0x0A00_0201, 0x0A00_02FF, "中国|0|福建省|福州市|电信"),
+        ];
+        let searcher = Searcher::from_bytes(crate::maker::make_xdb(&segments)).unwrap();
+        let closest_match = |ip: u32| {
+            let (distance, info) = searcher.closest_match(ip).unwrap();
+            (distance, info.city)
+        };
+        assert_eq!(closest_match(0x0A00_0010), (0, "内网IP".to_owned()));
+        assert_eq!(closest_match(0), (0x0A00_0000, "内网IP".to_owned()));
+        assert_eq!(closest_match(0x0A00_0110), (0x11, "内网IP".to_owned()));
+        assert_eq!(closest_match(0x0A00_01F0), (0x11, "福州市".to_owned()));
+        // the middle of the gap takes the lower segment
+        assert_eq!(closest_match(0x0A00_0180), (0x81, "内网IP".to_owned()));
+        assert_eq!(
+            closest_match(u32::MAX),
+            (u32::MAX - 0x0A00_02FF, "福州市".to_owned())
+        );
+
+        let searcher = Searcher::new(default_detect_xdb_file().unwrap()).unwrap();
+        let (distance, info) = searcher.closest_match(0x0100_0100).unwrap();
+        assert_eq!((distance, info.province.as_str()), (0, "福建省"));
+    }
+
+    #[test]
+    fn test_find_segment() {
+        let segments = [
+            (0x0100_0000u32, 0x0100_00FF, "澳大利亚|0|0|0|0"),
+            (0x0100_0200, 0x0100_02FF, "中国|0|福建省|福州市|电信"),
+        ];
+        let searcher = Searcher::from_bytes(crate::maker::make_xdb(&segments)).unwrap();
+        let segment = searcher.find_segment("1.0.0.1").unwrap();
+        assert_eq!(segment.start_addr(), Ipv4Addr::new(1, 0, 0, 0));
+        assert_eq!(segment.end_addr(), Ipv4Addr::new(1, 0, 0, 255));
+        assert_eq!(segment.info.country, "澳大利亚");
+        assert_eq!(
+            searcher.find_segment(0x0100_02FFu32).unwrap().end_ip,
+            0x0100_02FF
+        );
+        for ip in ["0.0.0.0", "1.0.1.0", "1.0.3.0"] {
+            assert!(matches!(
+                searcher.find_segment(ip),
+                Err(Ip2RegionError::NotMatched)
+            ));
+        }
+        assert!(matches!(
+            searcher.find_segment("1.0.0"),
+            Err(Ip2RegionError::InvalidIp(_))
+        ));
+
+        let searcher = Searcher::new(default_detect_xdb_file().unwrap()).unwrap();
+        let segment = searcher.find_segment("1.0.1.1").unwrap();
+        assert!(segment.start_ip <= 0x0100_0101 && 0x0100_0101 <= segment.end_ip);
+        assert_eq!(segment.info, searcher.search("1.0.1.1").unwrap());
+    }
+
+    #[test]
+    fn test_overlapping_and_gap_segments() {
+        let searcher = Searcher::new(default_detect_xdb_file().unwrap()).unwrap();
+        assert!(searcher.overlapping_segments().unwrap().is_empty());
+        assert!(searcher.gap_segments().unwrap().is_empty());
+
+        let segments = [
+            (0x0100_0000u32, 0x0100_00FF, "澳大利亚|0|0|0|0"),
+            (0x0100_0200, 0x0100_02FF, "中国|0|福建省|福州市|电信"),
+        ];
+        let searcher = Searcher::from_bytes(crate::maker::make_xdb(&segments)).unwrap();
+        assert!(searcher.overlapping_segments().unwrap().is_empty());
+        assert_eq!(
+            searcher.gap_segments().unwrap(),
+            [
+                (0, 0x00FF_FFFF),
+                (0x0100_0100, 0x0100_01FF),
+                (0x0100_0300, u32::MAX)
+            ]
+        );
+
+        // the maker never writes overlapping segments, swap the end ips of the two
+        // segment index entries in place
+        let mut buffer = crate::maker::make_xdb(&segments);
+        let first = buffer.len() - 2 * SEGMENT_INDEX_SIZE;
+        let second = first + SEGMENT_INDEX_SIZE;
+        buffer.copy_within(second + 4..second + 8, first + 4);
+        let searcher = Searcher::from_bytes(buffer).unwrap();
+        let overlapping = searcher.overlapping_segments().unwrap();
+        assert_eq!(overlapping.len(), 1);
+        assert_eq!(overlapping[0].0.end_ip, 0x0100_02FF);
+        assert_eq!(overlapping[0].1.start_ip, 0x0100_0200);
+        assert_eq!(
+            searcher.gap_segments().unwrap(),
+            [(0, 0x00FF_FFFF), (0x0100_0300, u32::MAX)]
+        );
+    }
+
+    #[test]
+    fn test_search_range() {
+        let segments = [
+            (0u32, 0x0A00_00FF, "0|0|0|0|0"),
+            (0x0A00_0100, 0x0A00_017F, "中国|0|广东省|深圳市|电信"),
+            (0x0A00_0180, 0x0A01_FFFF, "中国|0|广东省|深圳市|电信"),
+            (0x0A02_0000, u32::MAX, "美国|0|0|0|0"),
+        ];
+        let searcher = Searcher::from_bytes(crate::maker::make_xdb(&segments)).unwrap();
+        let search_range = |network: &str| {
+            searcher
+                .search_range(Ipv4Network::from_str(network).unwrap())
+                .unwrap()
+                .into_iter()
+                .map(|(block, info)| (block.to_string(), info.country))
+                .collect::<Vec<_>>()
+        };
+        let expected = |blocks: &[(&str, &str)]| {
+            blocks
+                .iter()
+                .map(|(block, country)| (block.to_string(), country.to_string()))
+                .collect::<Vec<_>>()
+        };
+        // the two segments of the same region are merged
+        assert_eq!(
+            search_range("10.0.0.0/22"),
+            expected(&[
+                ("10.0.0.0/24", "0"),
+                ("10.0.1.0/24", "中国"),
+                ("10.0.2.0/23", "中国")
+            ])
+        );
+        assert_eq!(
+            search_range("10.1.255.255/32"),
+            expected(&[("10.1.255.255/32", "中国")])
+        );
+        assert_eq!(
+            search_range("10.2.0.0/15"),
+            expected(&[("10.2.0.0/15", "美国")])
+        );
+
+        // every policy walks the same segments
+        let xdb_filepath = default_detect_xdb_file().unwrap();
+        let network = Ipv4Network::from_str("1.0.0.0/16").unwrap();
+        let searcher = Searcher::new(&xdb_filepath).unwrap();
+        let full = searcher.search_range(network).unwrap();
+        assert!(full.len() > 1);
+        for (block, info) in &full {
+            assert!(network.contains(block.network()));
+            assert_eq!(&searcher.search(block.network()).unwrap(), info);
+            assert_eq!(&searcher.search(block.broadcast()).unwrap(), info);
+        }
+        for cache_policy in [CachePolicy::Never, CachePolicy::VecIndex] {
+            let searcher = Searcher::with_cache_policy(&xdb_filepath, cache_policy).unwrap();
+            assert_eq!(searcher.search_range(network).unwrap(), full);
+        }
+    }
+}
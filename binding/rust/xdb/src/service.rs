@@ -0,0 +1,45 @@
+//! the searcher as a `tower::Service`, for composing it with the layers of the tower based stacks
+use std::future::{ready, Ready};
+use std::task::{Context, Poll};
+
+use tower_service::Service;
+
+use crate::{Ip2RegionError, IpInfo, Searcher, ToUIntIP};
+
+/// a lookup service always ready after the searcher is built, the future is already completed
+/// by `search` when it is returned, so `CachePolicy::Never` blocks on its file io like `search`
+impl<T: ToUIntIP> Service<T> for Searcher {
+    type Response = IpInfo;
+    type Error = Ip2RegionError;
+    type Future = Ready<Result<IpInfo, Ip2RegionError>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, ip: T) -> Self::Future {
+        ready(self.search(ip))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::poll_fn;
+
+    use super::*;
+    use crate::searcher::default_detect_xdb_file;
+
+    #[tokio::test]
+    async fn test_service() {
+        let mut searcher = Searcher::new(default_detect_xdb_file().unwrap()).unwrap();
+        poll_fn(|cx| Service::<u32>::poll_ready(&mut searcher, cx))
+            .await
+            .unwrap();
+        let info = searcher.call(0x0100_0100u32).await.unwrap();
+        assert_eq!(info, "中国|0|福建省|福州市|电信");
+        assert!(matches!(
+            searcher.call("1.0.1").await,
+            Err(Ip2RegionError::InvalidIp(_))
+        ));
+    }
+}
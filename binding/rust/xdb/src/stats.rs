@@ -0,0 +1,98 @@
+use std::time::Instant;
+
+use crate::format::last_search;
+use crate::{CachePolicy, Ip2RegionError, IpInfo, Searcher};
+
+/// how a single search of `Searcher::search_with_stats` went
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchStats {
+    /// the loop iterations of the binary search over the segment index, saturated at 255
+    pub iterations: u8,
+    /// the segment index was searched in memory, `CachePolicy::Full` or `CachePolicy::Mmap`,
+    /// instead of being read from the xdb file
+    pub cache_hit: bool,
+    /// the wall time of the whole search including parsing the region
+    pub elapsed_ns: u64,
+    /// the vector index cell `(il0, il1)` of the ip, its first and second bytes
+    pub vector_cell: (u8, u8),
+    /// the pointers of the first and the last segment index entries of the vector index cell
+    pub segment_start_ptr: u32,
+    pub segment_end_ptr: u32,
+    /// the pointer of the matched segment index entry
+    pub matched_segment_offset: u32,
+}
+
+impl Searcher {
+    /// same as `search`, with the stats to find out why some ips are slower than the others
+    pub fn search_with_stats(&self, ip: u32) -> Result<(IpInfo, SearchStats), Ip2RegionError> {
+        let cache_hit = match self.cache_policy() {
+            CachePolicy::Full => true,
+            #[cfg(feature = "memmap2")]
+            CachePolicy::Mmap => true,
+            CachePolicy::VecIndex | CachePolicy::Segmented(_) | CachePolicy::Never => false,
+        };
+        let start = Instant::now();
+        let info = self.search(ip)?;
+        let elapsed_ns = u64::try_from(start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+        let last = last_search();
+        let [il0, il1, _, _] = ip.to_be_bytes();
+        // the pointers were read as u32 from the xdb file
+        let stats = SearchStats {
+            iterations: u8::try_from(last.iterations).unwrap_or(u8::MAX),
+            cache_hit,
+            elapsed_ns,
+            vector_cell: (il0, il1),
+            segment_start_ptr: last.start_ptr as u32,
+            segment_end_ptr: last.end_ptr as u32,
+            matched_segment_offset: last.matched_ptr as u32,
+        };
+        Ok((info, stats))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::searcher::default_detect_xdb_file;
+
+    use super::*;
+
+    #[test]
+    fn test_search_with_stats() {
+        let xdb_filepath = default_detect_xdb_file().unwrap();
+        for (cache_policy, cache_hit) in [(CachePolicy::Full, true), (CachePolicy::Never, false)] {
+            let searcher = Searcher::with_cache_policy(&xdb_filepath, cache_policy).unwrap();
+            let (info, stats) = searcher
+                .search_with_stats(u32::from_be_bytes([1, 0, 1, 0]))
+                .unwrap();
+            assert_eq!(info, searcher.search("1.0.1.0").unwrap());
+            assert_eq!(stats.cache_hit, cache_hit);
+            assert!(stats.iterations > 0);
+            assert!(stats.elapsed_ns > 0);
+        }
+    }
+
+    #[test]
+    fn test_vector_cell() {
+        let searcher = Searcher::new(default_detect_xdb_file().unwrap()).unwrap();
+        let vector_index = searcher.get_vector_index_cache().unwrap();
+        for ip in [0x0100_0100u32, 0x7F00_0001, 0xDEAD_BEEF] {
+            let (_, stats) = searcher.search_with_stats(ip).unwrap();
+            let vector_cell = (((ip >> 24) & 0xFF) as u8, ((ip >> 16) & 0xFF) as u8);
+            assert_eq!(stats.vector_cell, vector_cell);
+
+            // the pointers of the vector index entry of the cell
+            let offset = (usize::from(vector_cell.0) * 256 + usize::from(vector_cell.1)) * 8;
+            let ptr = |offset: usize| {
+                u32::from_le_bytes(vector_index[offset..offset + 4].try_into().unwrap())
+            };
+            assert_eq!(stats.segment_start_ptr, ptr(offset));
+            assert_eq!(stats.segment_end_ptr, ptr(offset + 4));
+            assert!(stats.segment_start_ptr <= stats.matched_segment_offset);
+            assert!(stats.matched_segment_offset <= stats.segment_end_ptr);
+            assert_eq!(
+                (stats.matched_segment_offset - stats.segment_start_ptr) % 14,
+                0
+            );
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! the iana time zones of the chinese provinces, a compile time table
+use phf::phf_map;
+
+use crate::ip_info::strip_division_suffix;
+use crate::IpInfo;
+
+/// the provinces without their suffix, the mainland is `Asia/Shanghai` of the beijing time but
+/// xinjiang, which has the zone `Asia/Urumqi` of its local time in the iana database
+static TIME_ZONES: phf::Map<&'static str, &'static str> = phf_map! {
+    "北京" => "Asia/Shanghai",
+    "天津" => "Asia/Shanghai",
+    "上海" => "Asia/Shanghai",
+    "重庆" => "Asia/Shanghai",
+    "河北" => "Asia/Shanghai",
+    "山西" => "Asia/Shanghai",
+    "辽宁" => "Asia/Shanghai",
+    "吉林" => "Asia/Shanghai",
+    "黑龙江" => "Asia/Shanghai",
+    "江苏" => "Asia/Shanghai",
+    "浙江" => "Asia/Shanghai",
+    "安徽" => "Asia/Shanghai",
+    "福建" => "Asia/Shanghai",
+    "江西" => "Asia/Shanghai",
+    "山东" => "Asia/Shanghai",
+    "河南" => "Asia/Shanghai",
+    "湖北" => "Asia/Shanghai",
+    "湖南" => "Asia/Shanghai",
+    "广东" => "Asia/Shanghai",
+    "海南" => "Asia/Shanghai",
+    "四川" => "Asia/Shanghai",
+    "贵州" => "Asia/Shanghai",
+    "云南" => "Asia/Shanghai",
+    "陕西" => "Asia/Shanghai",
+    "甘肃" => "Asia/Shanghai",
+    "青海" => "Asia/Shanghai",
+    "内蒙古" => "Asia/Shanghai",
+    "广西" => "Asia/Shanghai",
+    "西藏" => "Asia/Shanghai",
+    "宁夏" => "Asia/Shanghai",
+    "新疆" => "Asia/Urumqi",
+    "香港" => "Asia/Hong_Kong",
+    "澳门" => "Asia/Macau",
+    "台湾" => "Asia/Taipei",
+};
+
+impl IpInfo {
+    /// the iana time zone of the province like `Asia/Shanghai`, `None` out of china or when
+    /// the province is unknown
+    pub fn time_zone(&self) -> Option<&'static str> {
+        if self.country != "中国" {
+            return None;
+        }
+        TIME_ZONES
+            .get(strip_division_suffix(&self.province))
+            .copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_zone() {
+        let time_zone = |raw: &str| raw.parse::<IpInfo>().unwrap().time_zone();
+        assert_eq!(
+            time_zone("中国|0|福建省|福州市|电信"),
+            Some("Asia/Shanghai")
+        );
+        assert_eq!(time_zone("中国|0|北京|北京市|联通"), Some("Asia/Shanghai"));
+        assert_eq!(time_zone("中国|0|新疆|乌鲁木齐|电信"), Some("Asia/Urumqi"));
+        assert_eq!(time_zone("中国|0|香港|0|0"), Some("Asia/Hong_Kong"));
+        assert_eq!(time_zone("中国|0|台湾省|台北|0"), Some("Asia/Taipei"));
+        assert_eq!(time_zone("中国|0|0|0|0"), None);
+        assert_eq!(time_zone("美国|0|加利福尼亚|0|0"), None);
+    }
+}
@@ -0,0 +1,108 @@
+//! check a searcher against the `start_ip|end_ip|region` lines of a test file like
+//! `data/ip.test.txt`, for the database integrity tests
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::net::Ipv4Addr;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::searcher::open_error;
+use crate::{Ip2RegionError, Searcher};
+
+/// the result of `validate_against_testfile`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// the number of the ips checked
+    pub total: u64,
+    /// the `(ip, expected region, searched region)` of every ip not matching its line, the
+    /// searched region is empty when the ip is not matched
+    pub mismatched: Vec<(Ipv4Addr, String, String)>,
+}
+
+impl ValidationReport {
+    /// no ip mismatched
+    pub fn is_valid(&self) -> bool {
+        self.mismatched.is_empty()
+    }
+}
+
+/// search every ip of every `start_ip|end_ip|region` line of the test file at `path`, the
+/// lines without a `|` are skipped, an ip that can not be parsed is `InvalidIp`
+pub fn validate_against_testfile(
+    searcher: &Searcher,
+    path: &Path,
+) -> Result<ValidationReport, Ip2RegionError> {
+    let file = File::open(path).map_err(|e| open_error(path, e))?;
+    let parse_ip =
+        |ip: &str| Ipv4Addr::from_str(ip).map_err(|_| Ip2RegionError::InvalidIp(ip.to_owned()));
+
+    let mut report = ValidationReport::default();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        let mut fields = line.trim_end_matches('\r').splitn(3, '|');
+        let (Some(start_ip), Some(end_ip), Some(expected)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        let (start_ip, end_ip) = (u32::from(parse_ip(start_ip)?), u32::from(parse_ip(end_ip)?));
+        for ip in start_ip..=end_ip {
+            report.total += 1;
+            let searched = match searcher.search(ip) {
+                Ok(info) if info == expected => continue,
+                Ok(info) => info.to_string(),
+                Err(Ip2RegionError::NotMatched) => String::new(),
+                Err(e) => return Err(e),
+            };
+            report
+                .mismatched
+                .push((Ipv4Addr::from(ip), expected.to_owned(), searched));
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_against_testfile() {
+        let segments = [
+            (0u32, 0x0100_00FF, "澳大利亚|0|0|0|0"),
+            (0x0100_0100, u32::MAX, "中国|0|福建省|福州市|电信"),
+        ];
+        let searcher = Searcher::from_bytes(crate::maker::make_xdb(&segments)).unwrap();
+        let path = std::env::temp_dir().join("xdb_test_validate.txt");
+        std::fs::write(
+            &path,
+            "1.0.0.0|1.0.0.255|澳大利亚|0|0|0|0\r\n\
+             \n\
+             1.0.1.0|1.0.1.1|中国|0|福建省|福州市|电信\n\
+             1.0.0.254|1.0.1.0|澳大利亚|0|0|0|0\n",
+        )
+        .unwrap();
+        let report = validate_against_testfile(&searcher, &path).unwrap();
+        assert_eq!(report.total, 256 + 2 + 3);
+        assert!(!report.is_valid());
+        assert_eq!(
+            report.mismatched,
+            [(
+                Ipv4Addr::new(1, 0, 1, 0),
+                "澳大利亚|0|0|0|0".to_owned(),
+                "中国|0|福建省|福州市|电信".to_owned()
+            )]
+        );
+
+        std::fs::write(&path, "1.0.0.0|1.0.0.x|0|0|0|0|0\n").unwrap();
+        assert!(matches!(
+            validate_against_testfile(&searcher, &path),
+            Err(Ip2RegionError::InvalidIp(ip)) if ip == "1.0.0.x"
+        ));
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            validate_against_testfile(&searcher, &path),
+            Err(Ip2RegionError::FileNotFound(_))
+        ));
+    }
+}
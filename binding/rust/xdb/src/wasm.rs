@@ -0,0 +1,65 @@
+//! the `wasm-bindgen` bindings for browsers and edge scripts, there is no file system there so
+//! the whole xdb file is passed in as a `Uint8Array`
+use std::sync::OnceLock;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Ip2RegionError, IpInfo, Searcher};
+
+static WASM_SEARCHER: OnceLock<Searcher> = OnceLock::new();
+
+/// the `Searcher` class of javascript
+#[wasm_bindgen(js_name = Searcher)]
+pub struct WasmSearcher(Searcher);
+
+#[wasm_bindgen(js_class = Searcher)]
+impl WasmSearcher {
+    /// `new Searcher(xdb)`, the buffer is copied into the wasm memory
+    #[wasm_bindgen(constructor)]
+    pub fn new(xdb: &[u8]) -> Result<WasmSearcher, JsError> {
+        Ok(Self(Searcher::from_bytes(xdb.to_vec())?))
+    }
+
+    /// search an ip string or an ipv4 number, returns `{country, region, province, city, isp}`
+    pub fn search(&self, ip: JsValue) -> Result<JsValue, JsError> {
+        search_js(&self.0, ip)
+    }
+}
+
+/// load the global searcher of `searchByIp`, only the first call takes effect
+#[wasm_bindgen]
+pub fn init(xdb: &[u8]) -> Result<(), JsError> {
+    if WASM_SEARCHER.get().is_none() {
+        let _ = WASM_SEARCHER.set(Searcher::from_bytes(xdb.to_vec())?);
+    }
+    Ok(())
+}
+
+/// the javascript `searchByIp` of `search_by_ip`, `init` must be called first
+#[wasm_bindgen(js_name = searchByIp)]
+pub fn search_by_ip(ip: JsValue) -> Result<JsValue, JsError> {
+    let searcher = WASM_SEARCHER
+        .get()
+        .ok_or_else(|| JsError::new("call init with the xdb file first"))?;
+    search_js(searcher, ip)
+}
+
+fn search_js(searcher: &Searcher, ip: JsValue) -> Result<JsValue, JsError> {
+    let info = search_value(searcher, &ip)?;
+    Ok(serde_wasm_bindgen::to_value(&info)?)
+}
+
+fn search_value(searcher: &Searcher, ip: &JsValue) -> Result<IpInfo, Ip2RegionError> {
+    if let Some(ip) = ip.as_string() {
+        if ip.contains(':') {
+            return searcher.search_by_ipv6(ip.as_str());
+        }
+        return searcher.search(ip.as_str());
+    }
+    match ip.as_f64() {
+        Some(ip) if ip.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(&ip) => {
+            searcher.search(ip as u32)
+        }
+        _ => Err(Ip2RegionError::InvalidIp(format!("{ip:?}"))),
+    }
+}
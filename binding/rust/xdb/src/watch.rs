@@ -0,0 +1,156 @@
+//! reload a searcher when its xdb file is replaced, for the services updating the xdb file in
+//! place without a restart
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{Ip2RegionError, Searcher};
+
+/// the quiet time after the last change before reloading, a file is usually written by
+/// several writes and each of them is an event
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// the background watcher of `Searcher::watch_file`, dropping it stops the watcher and joins
+/// its thread
+pub struct WatchHandle {
+    watcher: Option<RecommendedWatcher>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for WatchHandle {
+    fn drop(&mut self) {
+        // the event sender is owned by the watcher, dropping it ends the loop of the thread
+        drop(self.watcher.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Searcher {
+    /// spawn a thread reloading the searcher by `reload` whenever the xdb file at
+    /// `xdb_filepath` is created, written or renamed to, the result of every reload is
+    /// passed to `callback`, along with the errors of the watcher itself.
+    ///
+    /// the swap is atomic like `reload`, the searches in flight finish on the old xdb file
+    /// and the new ones see the new file. a reload of a half written file fails and keeps
+    /// the old one, so replace the file by a rename to avoid the extra reloads.
+    ///
+    /// the directory of the file is watched, so the file can be deleted and created again,
+    /// keep the returned handle as long as the file should be watched
+    pub fn watch_file<P, F>(
+        self: &Arc<Self>,
+        xdb_filepath: P,
+        callback: F,
+    ) -> Result<WatchHandle, Ip2RegionError>
+    where
+        P: AsRef<Path>,
+        F: FnMut(Result<(), Ip2RegionError>) + Send + 'static,
+    {
+        let xdb_filepath = xdb_filepath.as_ref();
+        let directory = match xdb_filepath.parent() {
+            Some(directory) if !directory.as_os_str().is_empty() => directory.canonicalize()?,
+            _ => std::env::current_dir()?,
+        };
+        let file_name = xdb_filepath
+            .file_name()
+            .ok_or_else(|| Ip2RegionError::FileNotFound(xdb_filepath.to_path_buf()))?;
+        let xdb_filepath = directory.join(file_name);
+
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            // the receiver is only gone when the thread has stopped
+            let _ = sender.send(event);
+        })?;
+        watcher.watch(&directory, RecursiveMode::NonRecursive)?;
+
+        let searcher = Arc::clone(self);
+        let thread = thread::Builder::new()
+            .name("xdb-watch".to_owned())
+            .spawn(move || watch_loop(&searcher, &xdb_filepath, &receiver, callback))?;
+        Ok(WatchHandle {
+            watcher: Some(watcher),
+            thread: Some(thread),
+        })
+    }
+}
+
+fn watch_loop<F>(
+    searcher: &Searcher,
+    xdb_filepath: &Path,
+    receiver: &Receiver<notify::Result<Event>>,
+    mut callback: F,
+) where
+    F: FnMut(Result<(), Ip2RegionError>),
+{
+    while let Ok(event) = receiver.recv() {
+        match event {
+            Ok(event) if is_change_of(&event, xdb_filepath) => {}
+            Ok(_) => continue,
+            Err(e) => {
+                callback(Err(e.into()));
+                continue;
+            }
+        }
+        // wait for the writes to settle, the other events meanwhile are folded in
+        loop {
+            match receiver.recv_timeout(DEBOUNCE) {
+                Ok(_) => {}
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+        callback(searcher.reload(xdb_filepath));
+    }
+}
+
+fn is_change_of(event: &Event, xdb_filepath: &Path) -> bool {
+    matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+        && event.paths.iter().any(|path| path == xdb_filepath)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::searcher::default_detect_xdb_file;
+
+    use super::*;
+
+    #[test]
+    fn test_watch_file() {
+        let directory = std::env::temp_dir().join("xdb_test_watch_file");
+        fs::create_dir_all(&directory).unwrap();
+        let xdb_filepath = directory.join("ip2region.xdb");
+        fs::copy(default_detect_xdb_file().unwrap(), &xdb_filepath).unwrap();
+        let searcher = Arc::new(Searcher::new(&xdb_filepath).unwrap());
+        assert_eq!(searcher.search("1.0.1.0").unwrap().country, "中国");
+
+        let (sender, receiver) = mpsc::channel();
+        let handle = searcher
+            .watch_file(&xdb_filepath, move |result| {
+                let _ = sender.send(result);
+            })
+            .unwrap();
+
+        // replace the file by a rename
+        let segments = [(0u32, u32::MAX, "美国|0|0|0|0")];
+        let new_filepath = directory.join("ip2region.xdb.new");
+        fs::write(&new_filepath, crate::maker::make_xdb(&segments)).unwrap();
+        fs::rename(&new_filepath, &xdb_filepath).unwrap();
+        let result = receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(result.is_ok());
+        assert_eq!(searcher.search("1.0.1.0").unwrap().country, "美国");
+
+        // the callback and its sender are dropped with the joined thread, so this ends
+        drop(handle);
+        for result in receiver {
+            assert!(result.is_ok());
+        }
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}